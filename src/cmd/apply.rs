@@ -0,0 +1,179 @@
+use std::{
+  fs,
+  path::{Path, PathBuf},
+  time::Instant,
+};
+
+use anyhow::{Context, Result, bail};
+use walkdir::WalkDir;
+
+use crate::{
+  cmd::generate::{Indent, OutputFormat, get_extension_info, load_config, write_patch_file},
+  util::{
+    json_patch::{self, PatchData},
+    json5,
+    log::{self, LogLevel, log_info},
+    parallel,
+    patterns::PatternConfig,
+  },
+};
+
+/// 运行应用翻译的命令：去掉已翻译 patch 文件中字符串的翻译标记前缀，
+/// 按相同的相对路径写入输出目录
+pub fn run(translations: PathBuf, output: PathBuf, config_dir: Option<PathBuf>) -> Result<()> {
+  // 计时开始
+  let start_time = Instant::now();
+
+  // 只需要正则配置中的翻译标记
+  let (_, regex_config) = load_config(config_dir.as_deref(), Some(&translations))?;
+
+  // 遍历翻译目录中的所有 patch 文件
+  let patch_files: Vec<PathBuf> = WalkDir::new(&translations)
+    .sort_by_file_name()
+    .into_iter()
+    .filter_map(|e| e.ok())
+    .filter(|e| e.file_type().is_file() && e.path().extension().is_some_and(|ext| ext == "patch"))
+    .map(|e| e.into_path())
+    .collect();
+
+  // 并行读取、解析并去掉前缀
+  let results = parallel::par_map(patch_files, |file_path| {
+    let result = fs::read_to_string(&file_path)
+      .context("Failed to read file")
+      .and_then(|json_str| json5::parse(&json_str).context("Failed to parse file"))
+      .and_then(|mut patch_value| {
+        let relative_path = file_path.strip_prefix(&translations).unwrap_or(&file_path);
+        let (ext, source_path) = source_info(relative_path, &regex_config);
+        let marker = regex_config.resolve_marker(&ext, &source_path);
+        let counts = json_patch::strip_patch_markers(&mut patch_value, marker);
+        let patch_data = PatchData::from_value(patch_value)
+          .context("Not a patch file: expected an array of operations or batches")?;
+        Ok((patch_data, counts))
+      });
+    (file_path, result)
+  });
+
+  let mut failed_files = Vec::new();
+  let (mut total_stripped, mut total_untouched) = (0, 0);
+  for (file_path, result) in results {
+    let (patch_data, (stripped, untouched)) = match result {
+      Ok(stripped_patch) => stripped_patch,
+      Err(e) => {
+        failed_files.push((file_path, e));
+        continue;
+      }
+    };
+    let relative_path = file_path.strip_prefix(&translations)?;
+    if log::enabled(LogLevel::Normal) {
+      println!(
+        "{} -> {} un-prefixed, {} untouched",
+        relative_path.display(),
+        stripped,
+        untouched
+      );
+    }
+    total_stripped += stripped;
+    total_untouched += untouched;
+
+    let output_file_path = output.join(relative_path);
+    write_patch_file(
+      &output_file_path,
+      &patch_data,
+      OutputFormat::Json,
+      Indent::default(),
+    )
+    .context(format!(
+      "[ERROR] Failed to write patch file to {}",
+      output_file_path.display()
+    ))?;
+  }
+
+  let duration = start_time.elapsed();
+  log_info!(
+    "Applied translations: {} string(s) un-prefixed, {} untouched - time elapsed: {}.{:03}s",
+    total_stripped,
+    total_untouched,
+    duration.as_secs(),
+    duration.subsec_millis()
+  );
+
+  // 汇总处理失败的文件
+  if !failed_files.is_empty() {
+    println!("[ERROR] {} file(s) failed:", failed_files.len());
+    for (file_path, e) in &failed_files {
+      println!("  {}: {:#}", file_path.display(), e);
+    }
+    bail!("[ERROR] {} file(s) failed to process!", failed_files.len());
+  }
+
+  Ok(())
+}
+
+/// 获取生成该 patch 文件的源文件后缀名和相对路径
+/// 如 "a.config.patch" 可能来自 "a.config.patch"，也可能来自 "a.config"
+fn source_info(relative_path: &Path, regex_config: &PatternConfig) -> (String, PathBuf) {
+  let (ext, _) = get_extension_info(relative_path);
+  if regex_config.contains_extension(&ext) {
+    return (ext, relative_path.to_path_buf());
+  }
+  let source_ext = ext.strip_suffix(".patch").unwrap_or(&ext).to_string();
+  (source_ext, relative_path.with_extension(""))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::{Value, json};
+
+  /// 为测试创建空的临时目录
+  fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("neki_lang-test-{}-{}", name, std::process::id()));
+    if dir.exists() {
+      fs::remove_dir_all(&dir).unwrap();
+    }
+    fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  /// 写入文件，自动创建上级目录
+  fn write(path: &Path, contents: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+  }
+
+  #[test]
+  fn prefixes_are_stripped_from_strings_and_arrays() {
+    let root = temp_dir("apply");
+    let (translations, output, config_dir) =
+      (root.join("tr"), root.join("out"), root.join("config"));
+    write(
+      &config_dir.join("regex_config.json"),
+      r#"{"codex": ["^/[a-z]+$"]}"#,
+    );
+    write(
+      &translations.join("codex/a.codex.patch"),
+      r#"[
+        {"op": "replace", "path": "/title", "value": "(T) 标题"},
+        {"op": "replace", "path": "/description", "value": "已经去掉前缀"},
+        {"op": "replace", "path": "/pages", "value": ["(T) 第一页", "第二页"]},
+        {"op": "remove", "path": "/unused"}
+      ]"#,
+    );
+
+    run(translations, output.clone(), Some(config_dir)).unwrap();
+
+    let applied: Value =
+      serde_json::from_str(&fs::read_to_string(output.join("codex/a.codex.patch")).unwrap())
+        .unwrap();
+    assert_eq!(
+      applied,
+      json!([
+        {"op": "replace", "path": "/title", "value": "标题"},
+        {"op": "replace", "path": "/description", "value": "已经去掉前缀"},
+        {"op": "replace", "path": "/pages", "value": ["第一页", "第二页"]},
+        {"op": "remove", "path": "/unused"}
+      ])
+    );
+    fs::remove_dir_all(&root).unwrap();
+  }
+}
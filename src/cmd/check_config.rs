@@ -0,0 +1,38 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+
+use crate::{
+  cmd::generate::read_config,
+  util::{
+    glob::DirWhitelist,
+    log::{log_info, log_warn},
+  },
+};
+
+/// 运行检查配置的命令：按与生成命令相同的查找顺序加载配置文件，
+/// 解析文件夹白名单和正则配置并编译所有正则，不处理任何输入文件
+pub fn run(config_dir: Option<PathBuf>, input: Option<PathBuf>) -> Result<()> {
+  let (dir_whitelist, regex_config, source) =
+    read_config(config_dir.as_deref(), input.as_deref()).context("[ERROR] Config check failed!")?;
+  log_info!("{}", source);
+
+  // 白名单中的 glob 同样需要编译
+  DirWhitelist::new(&dir_whitelist)
+    .context("[ERROR] Config check failed: invalid pattern in dir whitelist!")?;
+
+  // 提示可能写错的正则
+  for warning in regex_config.diagnostics() {
+    log_warn!("Regex config: {}", warning);
+  }
+
+  println!(
+    "Config OK: {} whitelist entr{}, {} extension(s), {} pattern(s)",
+    dir_whitelist.len(),
+    if dir_whitelist.len() == 1 { "y" } else { "ies" },
+    regex_config.extensions().count(),
+    regex_config.pattern_count()
+  );
+
+  Ok(())
+}
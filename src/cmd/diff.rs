@@ -0,0 +1,186 @@
+use std::{
+  collections::HashSet,
+  fs,
+  path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, bail};
+use indexmap::IndexMap;
+use serde_json::Value;
+use walkdir::WalkDir;
+
+use crate::{
+  cmd::generate::{
+    OutputFormat, PatchNaming, WalkOptions, collect_input_files, get_extension_info, load_config,
+    output_file_path,
+  },
+  util::{
+    json_patch::{self, PatchData, PatchOptions},
+    json5,
+    log::log_info,
+    parallel,
+  },
+};
+
+/// 运行对比命令：在内存中重新生成 patch，与输出目录中已有的文件按操作路径对比
+/// test operation 不参与对比，因此是否使用 -t 生成的输出都可以对比
+pub fn run(input: PathBuf, output: PathBuf, config_dir: Option<PathBuf>) -> Result<()> {
+  // 加载配置文件，使用与生成命令相同的白名单和后缀名过滤
+  let (dir_whitelist, regex_config) = load_config(config_dir.as_deref(), Some(&input))?;
+  let input_files = collect_input_files(
+    &input,
+    &dir_whitelist,
+    &regex_config,
+    &WalkOptions::default(),
+  )?;
+
+  // 并行读取、解析文件并生成 patch
+  let results = parallel::par_map(input_files, |file_path| {
+    let result = fs::read_to_string(&file_path)
+      .context("Failed to read file")
+      .and_then(|json_str| json5::parse(&json_str).context("Failed to parse file"))
+      .and_then(|json_value| {
+        let (ext, is_patch) = get_extension_info(&file_path);
+        let relative_path = file_path.strip_prefix(&input)?;
+        let patch_data = json_patch::generate_patch(
+          is_patch,
+          &json_value,
+          &ext,
+          relative_path,
+          &regex_config,
+          None,
+          &PatchOptions::default(),
+        );
+        let output_file_path = output_file_path(
+          &output,
+          relative_path,
+          is_patch,
+          PatchNaming::Append,
+          OutputFormat::Json,
+        );
+        Ok((output_file_path, patch_data))
+      });
+    (file_path, result)
+  });
+
+  let mut generated_files = HashSet::new();
+  let (mut changed_count, mut unchanged_count, mut new_count) = (0, 0, 0);
+  let mut failed_files = Vec::new();
+  for (file_path, result) in results {
+    let (output_file_path, patch_data) = match result {
+      Ok(generated) => generated,
+      Err(e) => {
+        failed_files.push((file_path, e));
+        continue;
+      }
+    };
+    if patch_data.is_empty() {
+      continue;
+    }
+    generated_files.insert(output_file_path.clone());
+    let relative_path = output_file_path.strip_prefix(&output)?;
+
+    // 输出目录中还没有该文件
+    if !output_file_path.exists() {
+      println!("{} (new file)", relative_path.display());
+      new_count += 1;
+      continue;
+    }
+    let existing = match read_patch_file(&output_file_path) {
+      Ok(existing) => existing,
+      Err(e) => {
+        failed_files.push((output_file_path, e));
+        continue;
+      }
+    };
+
+    let changes = diff_operations(&existing, &patch_data);
+    if changes.is_empty() {
+      unchanged_count += 1;
+    } else {
+      println!("{}", relative_path.display());
+      for (mark, path) in &changes {
+        println!("  {} {}", mark, path);
+      }
+      changed_count += 1;
+    }
+  }
+
+  // 输出目录中已没有对应源文件的 patch 文件
+  let stale_files: Vec<PathBuf> = WalkDir::new(&output)
+    .sort_by_file_name()
+    .into_iter()
+    .filter_map(|e| e.ok())
+    .filter(|e| e.file_type().is_file() && e.path().extension().is_some_and(|ext| ext == "patch"))
+    .map(|e| e.into_path())
+    .filter(|file_path| !generated_files.contains(file_path))
+    .collect();
+  for file_path in &stale_files {
+    println!(
+      "{} (no longer generated)",
+      file_path.strip_prefix(&output)?.display()
+    );
+  }
+
+  log_info!(
+    "{} file(s) changed, {} unchanged, {} new, {} no longer generated",
+    changed_count,
+    unchanged_count,
+    new_count,
+    stale_files.len()
+  );
+
+  // 汇总处理失败的文件
+  if !failed_files.is_empty() {
+    println!("[ERROR] {} file(s) failed:", failed_files.len());
+    for (file_path, e) in &failed_files {
+      println!("  {}: {:#}", file_path.display(), e);
+    }
+    bail!("[ERROR] {} file(s) failed to process!", failed_files.len());
+  }
+
+  Ok(())
+}
+
+/// 读取已有的 patch 文件
+fn read_patch_file(file_path: &Path) -> Result<PatchData> {
+  let json_str = fs::read_to_string(file_path).context("Failed to read file")?;
+  let patch_value = json5::parse(&json_str).context("Failed to parse file")?;
+  PatchData::from_value(patch_value)
+    .context("Not a patch file: expected an array of operations or batches")
+}
+
+/// 按操作路径对比两个 patch，返回（"+" 新增 / "-" 删除 / "~" 改变, 路径）
+fn diff_operations(existing: &PatchData, generated: &PatchData) -> Vec<(&'static str, String)> {
+  let existing = operations_by_path(existing);
+  let generated = operations_by_path(generated);
+  let mut changes = Vec::new();
+  for (path, patch_operation) in &generated {
+    match existing.get(path) {
+      None => changes.push(("+", path.clone())),
+      Some(existing_operation) if existing_operation != patch_operation => {
+        changes.push(("~", path.clone()))
+      }
+      Some(_) => {}
+    }
+  }
+  for path in existing.keys() {
+    if !generated.contains_key(path) {
+      changes.push(("-", path.clone()));
+    }
+  }
+  changes
+}
+
+/// 路径 -> 操作（忽略 test operation）
+fn operations_by_path(patch_data: &PatchData) -> IndexMap<String, &Value> {
+  patch_data
+    .operations()
+    .into_iter()
+    .filter(|patch_operation| patch_operation["op"] != "test")
+    .map(|patch_operation| {
+      let path = patch_operation["path"].as_str().unwrap_or_default();
+      (path.to_string(), patch_operation)
+    })
+    .collect()
+}
@@ -0,0 +1,161 @@
+use std::{fs, path::PathBuf, time::Instant};
+
+use anyhow::{Context, Result, bail};
+
+use crate::{
+  cmd::generate::{WalkOptions, collect_input_files, get_extension_info, load_config},
+  util::{
+    csv,
+    json_patch::{self, PatchOptions},
+    json5,
+    log::log_info,
+    parallel,
+  },
+};
+
+/// CSV 文件的表头
+pub(crate) const CSV_HEADER: [&str; 4] = ["source_file", "json_pointer", "original", "translation"];
+
+/// 运行导出 CSV 的命令：每个待翻译字符串一行，translation 列留空
+/// 数组值按下标展开为多行，JSON Pointer 末尾为下标
+pub fn run(input: PathBuf, output: PathBuf, config_dir: Option<PathBuf>) -> Result<()> {
+  // 计时开始
+  let start_time = Instant::now();
+
+  // 加载配置文件，使用与生成命令相同的白名单和后缀名过滤
+  let (dir_whitelist, regex_config) = load_config(config_dir.as_deref(), Some(&input))?;
+  let input_files = collect_input_files(
+    &input,
+    &dir_whitelist,
+    &regex_config,
+    &WalkOptions::default(),
+  )?;
+
+  // 并行读取、解析文件并提取字符串
+  let results = parallel::par_map(input_files, |file_path| {
+    let result = fs::read_to_string(&file_path)
+      .context("Failed to read file")
+      .and_then(|json_str| json5::parse(&json_str).context("Failed to parse file"))
+      .map(|json_value| {
+        let (ext, is_patch) = get_extension_info(&file_path);
+        let relative_path = file_path.strip_prefix(&input).unwrap_or(&file_path);
+        let patch_data = json_patch::generate_patch(
+          is_patch,
+          &json_value,
+          &ext,
+          relative_path,
+          &regex_config,
+          None,
+          &PatchOptions::default(),
+        );
+        json_patch::marked_strings(
+          &patch_data,
+          regex_config.resolve_marker(&ext, relative_path),
+        )
+      });
+    (file_path, result)
+  });
+
+  let mut csv_str = csv::write_record(&CSV_HEADER);
+  let mut row_count = 0;
+  let mut failed_files = Vec::new();
+  for (file_path, result) in results {
+    match result {
+      Ok(strings) => {
+        let relative_path = file_path
+          .strip_prefix(&input)?
+          .to_string_lossy()
+          .replace('\\', "/");
+        for (pointer, value) in strings {
+          csv_str.push_str(&csv::write_record(&[&relative_path, &pointer, &value, ""]));
+          row_count += 1;
+        }
+      }
+      Err(e) => failed_files.push((file_path, e)),
+    }
+  }
+
+  if let Some(parent) = output.parent()
+    && !parent.as_os_str().is_empty()
+  {
+    fs::create_dir_all(parent)?;
+  }
+  fs::write(&output, csv_str).context(format!(
+    "[ERROR] Failed to write CSV file to {}",
+    output.display()
+  ))?;
+
+  let duration = start_time.elapsed();
+  log_info!(
+    "Exported {} string(s) to {} - time elapsed: {}.{:03}s",
+    row_count,
+    output.display(),
+    duration.as_secs(),
+    duration.subsec_millis()
+  );
+
+  // 汇总处理失败的文件
+  if !failed_files.is_empty() {
+    println!("[ERROR] {} file(s) failed:", failed_files.len());
+    for (file_path, e) in &failed_files {
+      println!("  {}: {:#}", file_path.display(), e);
+    }
+    bail!("[ERROR] {} file(s) failed to process!", failed_files.len());
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::path::Path;
+
+  /// 为测试创建空的临时目录
+  fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("neki_lang-test-{}-{}", name, std::process::id()));
+    if dir.exists() {
+      fs::remove_dir_all(&dir).unwrap();
+    }
+    fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  /// 写入文件，自动创建上级目录
+  fn write(path: &Path, contents: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+  }
+
+  #[test]
+  fn exports_one_escaped_row_per_string() {
+    let root = temp_dir("export-csv");
+    let (input, config_dir, output) = (root.join("mod"), root.join("config"), root.join("out.csv"));
+    write(
+      &config_dir.join("regex_config.json"),
+      r#"{"codex": ["^/[a-z]+$"]}"#,
+    );
+    write(
+      &input.join("codex/a.codex"),
+      r#"{"title": "Say \"hi\", friend", "description": "line 1\nline 2", "pages": ["one", "two"]}"#,
+    );
+
+    run(input, output.clone(), Some(config_dir)).unwrap();
+
+    let csv_str = fs::read_to_string(&output).unwrap();
+    assert!(csv_str.contains(r#","Say ""hi"", friend","#), "{}", csv_str);
+    assert!(csv_str.contains("\"line 1\nline 2\""), "{}", csv_str);
+    let records = csv::parse(&csv_str).unwrap();
+    assert_eq!(records[0], CSV_HEADER);
+    assert_eq!(
+      records[1..],
+      [
+        ["codex/a.codex", "/title", "Say \"hi\", friend", ""],
+        ["codex/a.codex", "/description", "line 1\nline 2", ""],
+        ["codex/a.codex", "/pages/0", "one", ""],
+        ["codex/a.codex", "/pages/1", "two", ""],
+      ]
+    );
+    fs::remove_dir_all(&root).unwrap();
+  }
+}
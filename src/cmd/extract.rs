@@ -0,0 +1,145 @@
+use std::{
+  fs,
+  path::{Path, PathBuf},
+  time::Instant,
+};
+
+use anyhow::{Context, Result, bail};
+use indexmap::IndexMap;
+
+use crate::{
+  cmd::generate::{WalkOptions, collect_input_files, get_extension_info, load_config},
+  util::{
+    json_patch::{self, PatchOptions},
+    json5,
+    log::log_info,
+    parallel,
+  },
+};
+
+/// 运行导出命令：把所有待翻译字符串导出为一个 gettext PO 文件
+/// 相同的字符串只保留一个 msgid，并合并其引用位置
+pub fn run(input: PathBuf, output: PathBuf, config_dir: Option<PathBuf>) -> Result<()> {
+  // 计时开始
+  let start_time = Instant::now();
+
+  // 加载配置文件，使用与生成命令相同的白名单和后缀名过滤
+  let (dir_whitelist, regex_config) = load_config(config_dir.as_deref(), Some(&input))?;
+  let input_files = collect_input_files(
+    &input,
+    &dir_whitelist,
+    &regex_config,
+    &WalkOptions::default(),
+  )?;
+
+  // 并行读取、解析文件并提取字符串
+  let results = parallel::par_map(input_files, |file_path| {
+    let result = fs::read_to_string(&file_path)
+      .context("Failed to read file")
+      .and_then(|json_str| json5::parse(&json_str).context("Failed to parse file"))
+      .map(|json_value| {
+        let (ext, is_patch) = get_extension_info(&file_path);
+        let relative_path = file_path.strip_prefix(&input).unwrap_or(&file_path);
+        let patch_data = json_patch::generate_patch(
+          is_patch,
+          &json_value,
+          &ext,
+          relative_path,
+          &regex_config,
+          None,
+          &PatchOptions::default(),
+        );
+        json_patch::marked_strings(
+          &patch_data,
+          regex_config.resolve_marker(&ext, relative_path),
+        )
+      });
+    (file_path, result)
+  });
+
+  // 字符串 -> 引用位置（源文件相对路径:JSON Pointer）
+  let mut entries: IndexMap<String, Vec<String>> = IndexMap::new();
+  let mut failed_files = Vec::new();
+  for (file_path, result) in results {
+    match result {
+      Ok(strings) => {
+        let relative_path = file_path.strip_prefix(&input)?.to_string_lossy();
+        for (pointer, value) in strings {
+          entries
+            .entry(value)
+            .or_default()
+            .push(format!("{}:{}", relative_path, pointer));
+        }
+      }
+      Err(e) => failed_files.push((file_path, e)),
+    }
+  }
+
+  write_po_file(&output, &entries)?;
+
+  let duration = start_time.elapsed();
+  log_info!(
+    "Extracted {} unique string(s) to {} - time elapsed: {}.{:03}s",
+    entries.len(),
+    output.display(),
+    duration.as_secs(),
+    duration.subsec_millis()
+  );
+
+  // 汇总处理失败的文件
+  if !failed_files.is_empty() {
+    println!("[ERROR] {} file(s) failed:", failed_files.len());
+    for (file_path, e) in &failed_files {
+      println!("  {}: {:#}", file_path.display(), e);
+    }
+    bail!("[ERROR] {} file(s) failed to process!", failed_files.len());
+  }
+
+  Ok(())
+}
+
+/// 写入 PO 文件，每个字符串一个条目，msgstr 留空
+fn write_po_file(output: &Path, entries: &IndexMap<String, Vec<String>>) -> Result<()> {
+  let mut po = String::new();
+  // 头部条目，声明编码
+  po.push_str("msgid \"\"\n");
+  po.push_str("msgstr \"\"\n");
+  po.push_str("\"Content-Type: text/plain; charset=UTF-8\\n\"\n");
+
+  for (value, references) in entries {
+    po.push('\n');
+    for reference in references {
+      po.push_str(&format!("#: {}\n", reference));
+    }
+    po.push_str(&format!("msgid \"{}\"\n", escape_po_string(value)));
+    po.push_str("msgstr \"\"\n");
+  }
+
+  if let Some(parent) = output.parent()
+    && !parent.as_os_str().is_empty()
+  {
+    fs::create_dir_all(parent)?;
+  }
+  fs::write(output, po).context(format!(
+    "[ERROR] Failed to write PO file to {}",
+    output.display()
+  ))?;
+
+  Ok(())
+}
+
+/// 按 PO 格式转义字符串
+fn escape_po_string(value: &str) -> String {
+  let mut escaped = String::with_capacity(value.len());
+  for ch in value.chars() {
+    match ch {
+      '\\' => escaped.push_str("\\\\"),
+      '"' => escaped.push_str("\\\""),
+      '\n' => escaped.push_str("\\n"),
+      '\r' => escaped.push_str("\\r"),
+      '\t' => escaped.push_str("\\t"),
+      _ => escaped.push(ch),
+    }
+  }
+  escaped
+}
@@ -1,20 +1,28 @@
 use std::{
-  collections::HashSet,
+  collections::{BTreeMap, HashMap, HashSet},
   fs,
-  path::{Path, PathBuf},
-  time::Instant,
+  io::{self, Read},
+  path::{Component, Path, PathBuf},
+  time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
+use clap::ValueEnum;
 use indexmap::IndexMap;
-use walkdir::WalkDir;
+use serde::{Deserialize, Serialize};
+use serde_json::{Serializer, Value, json, ser::PrettyFormatter};
+use walkdir::{DirEntry, WalkDir};
 
 use crate::{
   cmd::shared::{DEFAULT_DIR_CONFIG, DEFAULT_REGEX_CONFIG},
   util::{
-    json_patch::{self, PatchData},
+    glob::{DirWhitelist, ExcludeGlobs},
+    json_patch::{self, NewlineMode, OpKind, PatchData, PatchOptions},
     json5,
-    patterns::{PatternConfig, RawPatternConfig},
+    log::{self, LogLevel, log_info, log_trace, log_verbose, log_warn},
+    parallel,
+    patterns::{NumericKeyPolicy, PatternConfig, RawPatternConfig},
+    random, text_diff, timestamp, yaml, zip,
   },
 };
 
@@ -25,29 +33,1177 @@ enum ConfigSource {
   External,
 }
 
+/// 单个文件的patch操作数超出上限时的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum)]
+pub enum MaxOpsAction {
+  /// 截断到上限数量
+  #[default]
+  Truncate,
+  /// 跳过整个文件
+  Skip,
+}
+
+/// 生成命令的选项
+#[derive(Debug, Default)]
+pub struct GenOptions {
+  /// 是否生成test operation
+  pub gen_test: bool,
+  /// test operation 同时检查原始值，基础文件中的字符串改变后 patch 不再生效
+  pub test_value: bool,
+  /// 单个文件允许的最大patch操作数，None表示不限制
+  pub max_ops_per_file: Option<usize>,
+  /// 超出上限时的处理方式
+  pub max_ops_action: MaxOpsAction,
+  /// 是否捕获源文件中翻译字段之前的注释
+  pub capture_comments: bool,
+  /// 是否把捕获的注释作为翻译上下文附加到 patch 操作中，否则只在 -v 时列出
+  pub annotate: bool,
+  /// 是否汇总跨文件重复的字符串，并输出全局字符串表
+  pub dedupe_across_files: bool,
+  /// 是否将生成的 patch 应用到源文件副本上，检查每个路径都能被正确解析
+  pub roundtrip_check: bool,
+  /// 路径经过纯数字对象键时的处理方式，None表示使用配置中的设置
+  pub numeric_key_policy: Option<NumericKeyPolicy>,
+  /// 反向 patch（恢复原始字符串）的输出目录，None表示不生成
+  pub reverse_dir: Option<PathBuf>,
+  /// 随机抽取并输出的字符串数量，None表示不抽样
+  pub sample: Option<usize>,
+  /// 抽样使用的随机种子，None表示使用当前时间
+  pub sample_seed: Option<u64>,
+  /// 是否为空字符串和纯空白字符串生成操作
+  pub include_blank: bool,
+  /// 遇到第一个出错的文件时立即中止
+  pub strict: bool,
+  /// 有文件出错时仍以成功状态退出
+  pub ignore_errors: bool,
+  /// 配置文件目录，None表示使用可执行文件目录
+  pub config_dir: Option<PathBuf>,
+  /// 只输出将要写入的文件及操作数，不写入磁盘
+  pub dry_run: bool,
+  /// 去掉单个文件中完全相同的 patch 操作
+  pub dedupe: bool,
+  /// 只处理在此时间之后修改过的文件，None表示处理全部文件
+  pub since: Option<SystemTime>,
+  /// 使用输出目录中上次运行的时间作为 since，并在运行后更新
+  pub since_last: bool,
+  /// 把所有 patch 合并写入该文件（以源文件相对路径为键），None表示按目录结构输出
+  pub single_file: Option<PathBuf>,
+  /// 按生成操作的正则分组输出所有操作，便于排查配置
+  pub explain: bool,
+  /// 运行结束后把 JSON 格式的运行报告写入该文件，None表示不输出
+  pub report: Option<PathBuf>,
+  /// 遍历输入目录时跟随符号链接
+  pub follow_symlinks: bool,
+  /// 排除相对路径匹配这些 glob 的文件或目录
+  pub exclude: Vec<String>,
+  /// 跳过大于该字节数的文件，0 表示不限制
+  pub max_file_size: u64,
+  /// 生成的操作类型
+  pub op_kind: OpKind,
+  /// 输出 patch 文件的格式
+  pub format: OutputFormat,
+  /// 按 path 排序每个文件中的操作
+  pub sort_ops: bool,
+  /// 非 patch 源文件的输出文件命名方式
+  pub naming: PatchNaming,
+  /// 生成的字符串值中换行符的处理方式
+  pub newlines: NewlineMode,
+  /// 写入前检查生成的 patch 格式是否正确
+  pub self_check: bool,
+  /// 递归按字母顺序排序输出中所有对象的键
+  pub sort_keys: bool,
+  /// 命令行指定的翻译标记前缀，优先于配置中的所有前缀
+  pub prefix: Option<String>,
+  /// 输出文件已存在且内容改变时，输出新旧内容的 diff
+  pub show_diff: bool,
+  /// JSON 输出的缩进
+  pub indent: Indent,
+  /// 合并为单个文件时在每个操作的指针前加上源文件的相对路径
+  pub relative_pointer: bool,
+  /// 允许输出目录与输入目录重叠，此时跳过输入目录中已生成的文件
+  pub allow_overlap: bool,
+  /// 遍历隐藏（名称以 '.' 开头）的文件和目录
+  pub include_hidden: bool,
+  /// 基础 Mod 目录，按其中对应文件是否存在路径生成 add 或 replace
+  pub base_dir: Option<PathBuf>,
+  /// 时间限制，超出后不再处理剩余的文件，写入已生成的部分后以失败退出
+  pub time_budget: Option<Duration>,
+}
+
+/// 输出 patch 文件的格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+  /// 格式化的 JSON
+  #[default]
+  Json,
+  /// 块格式的 YAML，文件名追加 ".yaml"
+  Yaml,
+}
+
+impl OutputFormat {
+  /// 追加在 patch 文件名后的后缀
+  fn suffix(&self) -> &'static str {
+    match self {
+      OutputFormat::Json => "",
+      OutputFormat::Yaml => ".yaml",
+    }
+  }
+}
+
+/// JSON 输出的缩进
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Indent {
+  /// 指定数量的空格
+  Spaces(usize),
+  /// 制表符
+  Tab,
+}
+
+impl Default for Indent {
+  fn default() -> Self {
+    Indent::Spaces(2)
+  }
+}
+
+impl Indent {
+  /// 每一级缩进使用的字符串
+  fn as_string(&self) -> String {
+    match self {
+      Indent::Spaces(count) => " ".repeat(*count),
+      Indent::Tab => "\t".to_string(),
+    }
+  }
+}
+
+/// 解析 --indent 参数：空格数量或 "tab"
+pub fn parse_indent(value: &str) -> Result<Indent, String> {
+  if value.eq_ignore_ascii_case("tab") {
+    return Ok(Indent::Tab);
+  }
+  value
+    .parse()
+    .map(Indent::Spaces)
+    .map_err(|_| format!("\"{}\" is neither a number of spaces nor \"tab\"", value))
+}
+
+/// 非 patch 源文件的输出文件命名方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum PatchNaming {
+  /// 在完整文件名后追加 ".patch"，如 "foo.json" -> "foo.json.patch"（Starbound 使用的命名）
+  #[default]
+  Append,
+  /// 用 ".patch" 替换最后一个后缀名，如 "foo.json" -> "foo.patch"，无后缀名时直接追加
+  Replace,
+}
+
+impl GenOptions {
+  /// 生成 patch 时使用的选项
+  fn patch_options(&self) -> PatchOptions {
+    PatchOptions {
+      include_blank: self.include_blank,
+      gen_test_operation: self.gen_test,
+      test_value: self.test_value,
+      op_kind: self.op_kind,
+      newlines: self.newlines,
+    }
+  }
+
+  /// 纯数字对象键的处理方式，优先级：命令行 > 正则配置 > 默认的 Warn
+  fn numeric_key_policy(&self, regex_config: &PatternConfig) -> NumericKeyPolicy {
+    self
+      .numeric_key_policy
+      .or(regex_config.numeric_key_policy())
+      .unwrap_or_default()
+  }
+}
+
+/// 记录上次运行时间的文件名（位于输出目录）
+const LAST_RUN_FILE: &str = ".neki-lastrun";
+
+/// 解析 --since 参数：已存在的文件取其修改时间，否则按 RFC 3339 时间戳解析
+pub fn parse_since(value: &str) -> Result<SystemTime, String> {
+  let path = Path::new(value);
+  if path.is_file() {
+    return fs::metadata(path)
+      .and_then(|metadata| metadata.modified())
+      .map_err(|e| format!("failed to read modification time of {}: {}", value, e));
+  }
+  timestamp::parse_rfc3339(value).ok_or_else(|| {
+    format!(
+      "\"{}\" is neither an existing file nor an RFC 3339 timestamp",
+      value
+    )
+  })
+}
+
+/// 解析 --time-budget 参数：正数秒数，可以带小数
+pub fn parse_time_budget(value: &str) -> Result<Duration, String> {
+  value
+    .parse::<f64>()
+    .ok()
+    .filter(|seconds| *seconds > 0.0)
+    .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+    .ok_or_else(|| format!("\"{}\" is not a positive number of seconds", value))
+}
+
 /// 运行生成JSON Patch即语言模板（Language Template）的命令
-pub fn run(input: PathBuf, output: PathBuf, test: bool) -> Result<()> {
+/// input 为单个文件时只处理该文件，输出到 output 目录中；为 .zip 压缩包时处理包内的文件
+pub fn run(input: PathBuf, output: PathBuf, options: GenOptions) -> Result<()> {
+  // 加载配置文件（文件夹白名单+正则表达式）
+  let (dir_whitelist, regex_config) = load_config(options.config_dir.as_deref(), Some(&input))?;
+  let regex_config = match &options.prefix {
+    Some(prefix) => regex_config.with_prefix_override(prefix),
+    None => regex_config,
+  };
+
+  if input.is_file()
+    && input
+      .extension()
+      .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"))
+  {
+    return run_archive(&input, &output, &options, &dir_whitelist, &regex_config);
+  }
+
+  if input.is_file() {
+    // 用户明确指定了文件，不使用文件夹白名单，但仍需配置了该后缀名
+    let (ext, _) = get_extension_info(&input);
+    if !regex_config.contains_extension(&ext) {
+      bail!(
+        "[ERROR] No patterns configured for extension \"{}\" of {}",
+        ext,
+        input.display()
+      );
+    }
+    let file_name = input
+      .file_name()
+      .context("[ERROR] Failed to get input file name!")?;
+    // 以文件所在目录为输入目录（用于计算输出的相对路径），只处理该文件，不遍历目录
+    let input_dir = input
+      .parent()
+      .filter(|parent| !parent.as_os_str().is_empty())
+      .unwrap_or(Path::new("."));
+    check_overlap(&input, &output, &options)?;
+    return generate(
+      input_dir,
+      &output,
+      &options,
+      InputSelection::File(input_dir.join(file_name)),
+      &regex_config,
+    );
+  }
+
+  check_overlap(&input, &output, &options)?;
+  run_with_config(&input, &output, &options, &dir_whitelist, &regex_config)
+}
+
+/// 输出目录与输入（目录或单个文件）互相包含时，遍历可能读到已生成的文件，写入也可能覆盖输入
+/// 未使用 --allow-overlap 时报错，否则给出警告
+fn check_overlap(input: &Path, output_dir: &Path, options: &GenOptions) -> Result<()> {
+  if !paths_overlap(input, output_dir) {
+    return Ok(());
+  }
+  if !options.allow_overlap {
+    bail!(
+      "[ERROR] Output directory {} overlaps input {}! Use a separate output directory or pass --allow-overlap",
+      output_dir.display(),
+      input.display()
+    );
+  }
+  log_warn!(
+    "Output directory {} overlaps input {}, previously generated files will be skipped",
+    output_dir.display(),
+    input.display()
+  );
+  Ok(())
+}
+
+/// 遍历输入目录，返回要处理的文件，并提示匹配不到任何路径的白名单条目
+fn walk_input_dir(
+  input_dir: &Path,
+  output_dir: &Path,
+  options: &GenOptions,
+  dir_whitelist: &HashSet<String>,
+  regex_config: &PatternConfig,
+) -> Result<Vec<PathBuf>> {
+  let mut missing_entries = DirWhitelist::new(dir_whitelist)
+    .context("[ERROR] Invalid pattern in dir whitelist!")?
+    .missing_entries(input_dir);
+  missing_entries.sort();
+  for entry in &missing_entries {
+    log_warn!(
+      "Whitelist entry \"{}\" matches nothing in {}",
+      entry,
+      input_dir.display()
+    );
+  }
+  if !dir_whitelist.is_empty() && missing_entries.len() == dir_whitelist.len() {
+    log_warn!(
+      "!!! None of the {} whitelist entries exist in {}, no files will be processed. Check dirs_config.json !!!",
+      dir_whitelist.len(),
+      input_dir.display()
+    );
+  }
+  let walk_options = WalkOptions {
+    follow_symlinks: options.follow_symlinks,
+    exclude: &options.exclude,
+    max_file_size: options.max_file_size,
+    include_hidden: options.include_hidden,
+  };
+  let mut input_files = collect_input_files(input_dir, dir_whitelist, regex_config, &walk_options)?;
+  if options.allow_overlap {
+    skip_generated_files(&mut input_files, input_dir, output_dir, options);
+  }
+  Ok(input_files)
+}
+
+/// 读取源文件在基础 Mod 中对应的文件，patch 文件对应去掉 ".patch" 后的原版资源
+/// 解析结果缓存在 base_cache 中，文件不存在或解析失败时返回 None（回退为 replace）
+fn load_base_file<'a>(
+  base_dir: &Path,
+  relative_path: &Path,
+  base_cache: &'a mut HashMap<PathBuf, Option<Value>>,
+) -> Option<&'a Value> {
+  let relative_path = relative_path.to_string_lossy();
+  let base_path = base_dir.join(
+    relative_path
+      .strip_suffix(".patch")
+      .unwrap_or(&relative_path),
+  );
+  base_cache
+    .entry(base_path)
+    .or_insert_with_key(|base_path| {
+      if !base_path.is_file() {
+        return None;
+      }
+      let parsed = fs::read_to_string(base_path)
+        .context("Failed to read file")
+        .and_then(|json_str| json5::parse(&json_str).context("Failed to parse file"));
+      match parsed {
+        Ok(base_value) => Some(base_value),
+        Err(e) => {
+          log_warn!("Ignored base file {}: {:#}", base_path.display(), e);
+          None
+        }
+      }
+    })
+    .as_ref()
+}
+
+/// 检查两个路径是否相同或互相包含（不存在的部分按字面比较）
+fn paths_overlap(a: &Path, b: &Path) -> bool {
+  let (a, b) = (canonicalize_lenient(a), canonicalize_lenient(b));
+  a.starts_with(&b) || b.starts_with(&a)
+}
+
+/// 规范化路径，路径不存在时规范化最近的已存在的上级目录，再拼接剩余部分
+fn canonicalize_lenient(path: &Path) -> PathBuf {
+  if let Ok(canonical) = fs::canonicalize(path) {
+    return canonical;
+  }
+  match (path.parent(), path.file_name()) {
+    (Some(parent), Some(file_name)) => {
+      let parent = if parent.as_os_str().is_empty() {
+        Path::new(".")
+      } else {
+        parent
+      };
+      canonicalize_lenient(parent).join(file_name)
+    }
+    _ => path.to_path_buf(),
+  }
+}
+
+/// 跳过输入目录中由本工具生成的文件：嵌套在输入目录中的输出目录下的文件，
+/// 以及会被其他输入文件的输出覆盖的文件。输入文件列表在写入前确定，本次写入的文件不会被处理
+fn skip_generated_files(
+  input_files: &mut Vec<PathBuf>,
+  input_dir: &Path,
+  output_dir: &Path,
+  options: &GenOptions,
+) {
+  let canonical_input = canonicalize_lenient(input_dir);
+  let canonical_output = canonicalize_lenient(output_dir);
+  let nested_output =
+    canonical_output != canonical_input && canonical_output.starts_with(&canonical_input);
+  let generated: HashSet<PathBuf> = input_files
+    .iter()
+    .filter_map(|file_path| {
+      let relative_path = file_path.strip_prefix(input_dir).ok()?;
+      let (_, is_patch) = get_extension_info(file_path);
+      let output_file_path = output_file_path(
+        output_dir,
+        relative_path,
+        is_patch,
+        options.naming,
+        options.format,
+      );
+      Some(canonicalize_lenient(&output_file_path))
+    })
+    .collect();
+  let total = input_files.len();
+  input_files.retain(|file_path| {
+    let canonical = canonicalize_lenient(file_path);
+    let in_nested_output = nested_output && canonical.starts_with(&canonical_output);
+    !in_nested_output && !generated.contains(&canonical)
+  });
+  if input_files.len() < total {
+    log_info!(
+      "Skipped {} previously generated file(s) in the input directory",
+      total - input_files.len()
+    );
+  }
+}
+
+/// 处理 ZIP 压缩包：把后缀名在配置中的条目解压到临时目录，按普通目录处理，
+/// 白名单和后缀名过滤作用于包内路径，输出目录与包内结构一致
+fn run_archive(
+  archive_path: &Path,
+  output_dir: &Path,
+  options: &GenOptions,
+  dir_whitelist: &HashSet<String>,
+  regex_config: &PatternConfig,
+) -> Result<()> {
+  let archive = zip::ZipArchive::open(archive_path).context(format!(
+    "[ERROR] Failed to open archive {}",
+    archive_path.display()
+  ))?;
+
+  let nanos = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_nanos())
+    .unwrap_or_default();
+  let extract_dir =
+    std::env::temp_dir().join(format!("neki_lang-{}-{}", std::process::id(), nanos));
+  let result = extract_archive(&archive, &extract_dir, regex_config).and_then(|entry_count| {
+    log_info!(
+      "Extracted {} entr{} from {}",
+      entry_count,
+      if entry_count == 1 { "y" } else { "ies" },
+      archive_path.display()
+    );
+    run_with_config(
+      &extract_dir,
+      output_dir,
+      options,
+      dir_whitelist,
+      regex_config,
+    )
+  });
+  // 无论是否成功都删除临时目录
+  if extract_dir.exists()
+    && let Err(e) = fs::remove_dir_all(&extract_dir)
+  {
+    log_warn!(
+      "Failed to remove temporary directory {}: {}",
+      extract_dir.display(),
+      e
+    );
+  }
+  result
+}
+
+/// 解压后缀名在配置中的条目，返回解压的条目数
+fn extract_archive(
+  archive: &zip::ZipArchive,
+  extract_dir: &Path,
+  regex_config: &PatternConfig,
+) -> Result<usize> {
+  let mut entry_count = 0;
+  for entry in archive.entries() {
+    if entry.is_dir() {
+      continue;
+    }
+    let relative_path = Path::new(&entry.name);
+    // 跳过可能写到临时目录之外的路径
+    if !relative_path
+      .components()
+      .all(|component| matches!(component, Component::Normal(_)))
+    {
+      log_warn!("Skipped archive entry with unsafe path: {}", entry.name);
+      continue;
+    }
+    let (ext, _) = get_extension_info(relative_path);
+    if !regex_config.contains_extension(&ext) {
+      continue;
+    }
+    let contents = archive
+      .read(entry)
+      .context("[ERROR] Failed to extract archive")?;
+    let file_path = extract_dir.join(relative_path);
+    fs::create_dir_all(
+      file_path
+        .parent()
+        .context("[ERROR] Failed to get parent directory!")?,
+    )?;
+    fs::write(&file_path, contents)?;
+    entry_count += 1;
+  }
+  Ok(entry_count)
+}
+
+/// 从标准输入读取单个文件，把生成的 patch 写到标准输出
+/// ext 为文件后缀名（patch 文件为 "<后缀名>.patch"），提示信息输出到标准错误
+pub fn run_stdin(ext: &str, options: GenOptions) -> Result<()> {
+  // stdout 只输出生成的 patch
+  log::set_stderr(true);
+  // 从当前目录向上查找项目配置
+  let (_, regex_config, source) = read_config(options.config_dir.as_deref(), Some(Path::new(".")))?;
+  let regex_config = match &options.prefix {
+    Some(prefix) => regex_config.with_prefix_override(prefix),
+    None => regex_config,
+  };
+  log_info!("{}", source);
+
+  let ext = ext.trim_start_matches('.').to_lowercase();
+  let is_patch = ext == "patch" || ext.ends_with(".patch");
+  if !regex_config.contains_extension(&ext) {
+    bail!("[ERROR] No patterns configured for extension \"{}\"", ext);
+  }
+
+  let mut json_str = String::new();
+  io::stdin()
+    .read_to_string(&mut json_str)
+    .context("[ERROR] Failed to read from stdin!")?;
+  let (json_value, comments) = if options.capture_comments {
+    json5::parse_with_comments(&json_str).map(|(v, c)| (v, Some(c)))
+  } else {
+    json5::parse(&json_str).map(|v| (v, None))
+  }
+  .context("[ERROR] Failed to parse stdin!")?;
+
+  let mut json_value_vec = json_patch::generate_patch(
+    is_patch,
+    &json_value,
+    &ext,
+    Path::new(""),
+    &regex_config,
+    comments.as_ref().filter(|_| options.annotate),
+    &options.patch_options(),
+  );
+  // 与处理目录时相同的后处理，没有对应的基础文件
+  if !post_process_patch(
+    Path::new("<stdin>"),
+    &json_value,
+    is_patch,
+    &mut json_value_vec,
+    None,
+    options.numeric_key_policy(&regex_config),
+    &options,
+  ) {
+    bail!("[ERROR] Stdin input exceeds --max-ops-per-file");
+  }
+  if let Some(comments) = &comments
+    && !options.annotate
+  {
+    list_comments(Path::new("<stdin>"), &json_value, &json_value_vec, comments);
+  }
+  if options.self_check {
+    json_patch::validate_patch(&json_value_vec)
+      .context("[ERROR] Self-check failed, the generated patch is malformed")?;
+  }
+  print!(
+    "{}",
+    patch_to_string(&json_value_vec, options.format, options.indent)?.trim_end()
+  );
+  println!();
+
+  Ok(())
+}
+
+/// 对单个文件生成的 patch 统一做后处理：检查纯数字对象键、按基础文件决定 op、去重、排序和检查操作数上限
+/// base_value 为基础 Mod 中对应的文件，返回 false 表示超出操作数上限而跳过该文件
+fn post_process_patch(
+  file_path: &Path,
+  json_value: &Value,
+  is_patch: bool,
+  json_value_vec: &mut PatchData,
+  base_value: Option<&Value>,
+  numeric_key_policy: NumericKeyPolicy,
+  options: &GenOptions,
+) -> bool {
+  // 检查路径中的纯数字对象键（patch文件的路径指向原版资源，无法检查）
+  if !is_patch && numeric_key_policy != NumericKeyPolicy::Allow {
+    json_value_vec.retain(|patch_operation| {
+      let path = patch_operation["path"].as_str().unwrap_or_default();
+      if !json_patch::has_numeric_object_key(json_value, path) {
+        return true;
+      }
+      if patch_operation["op"] != "test" {
+        log_warn!(
+          "{}: path \"{}\" ({}) contains a numeric object key{}",
+          file_path.display(),
+          path,
+          json_patch::pointer_to_human_in(json_value, path),
+          if numeric_key_policy == NumericKeyPolicy::Skip {
+            ", skipped."
+          } else {
+            ""
+          }
+        );
+      }
+      numeric_key_policy != NumericKeyPolicy::Skip
+    });
+  }
+  // 按基础 Mod 中对应文件是否存在该路径决定生成 add 还是 replace
+  if let Some(base_value) = base_value {
+    let add_count = json_value_vec.set_op_kinds_from_base(base_value);
+    if add_count > 0 {
+      log_verbose!(
+        "{}: {} path(s) missing from the base file, using add",
+        file_path.display(),
+        add_count
+      );
+    }
+  }
+  // 去掉重复的操作
+  if options.dedupe {
+    let removed = json_value_vec.dedup();
+    if removed > 0 {
+      log_info!(
+        "{}: removed {} duplicate operation(s)",
+        file_path.display(),
+        removed
+      );
+    }
+  }
+  log_trace!(
+    "{} -> {} operation(s)",
+    file_path.display(),
+    json_value_vec.entry_count()
+  );
+  // 按路径排序，源文件中键的顺序改变时输出保持稳定
+  if options.sort_ops {
+    json_value_vec.sort_by_path();
+  }
+  // 按键排序，输出不受源文件中键顺序的影响
+  if options.sort_keys {
+    json_value_vec.sort_keys();
+  }
+  // 检查操作数上限
+  if let Some(max_ops) = options.max_ops_per_file {
+    let op_count = json_value_vec.entry_count();
+    if op_count > max_ops {
+      match options.max_ops_action {
+        MaxOpsAction::Truncate => {
+          log_warn!(
+            "{} produced {} operations (limit {}), truncated.",
+            file_path.display(),
+            op_count,
+            max_ops
+          );
+          json_value_vec.truncate(max_ops);
+        }
+        MaxOpsAction::Skip => {
+          log_warn!(
+            "{} produced {} operations (limit {}), skipped.",
+            file_path.display(),
+            op_count,
+            max_ops
+          );
+          return false;
+        }
+      }
+    }
+  }
+  true
+}
+
+/// 要处理的输入文件
+enum InputSelection<'a> {
+  /// 遍历输入目录，处理白名单内的文件
+  Walk(&'a HashSet<String>),
+  /// 只处理指定的单个文件
+  File(PathBuf),
+}
+
+/// 使用已加载的配置运行生成命令，便于作为库调用
+/// input_dir: 输入的Neki Mod本体目录；output_dir: 输出的JSON Patch即语言模板目录
+pub fn run_with_config(
+  input_dir: &Path,
+  output_dir: &Path,
+  options: &GenOptions,
+  dir_whitelist: &HashSet<String>,
+  regex_config: &PatternConfig,
+) -> Result<()> {
+  generate(
+    input_dir,
+    output_dir,
+    options,
+    InputSelection::Walk(dir_whitelist),
+    regex_config,
+  )
+}
+
+/// 生成命令的主体，input_selection 决定遍历输入目录还是只处理单个文件
+fn generate(
+  input_dir: &Path,
+  output_dir: &Path,
+  options: &GenOptions,
+  input_selection: InputSelection,
+  regex_config: &PatternConfig,
+) -> Result<()> {
   // 1. 初始部分
   // 计时开始
   let start_time = Instant::now();
-  // 输入的Neki Mod本体目录
-  let input_dir = input.as_path();
-  // 输出的JSON Patch即语言模板目录
-  let output_dir = output.as_path();
-  // 是否生成test operation
-  let gen_test = test;
-
-  // 输入文件的 map
-  let mut input_files_map = IndexMap::new();
+  // 当前阶段的开始时间，各阶段分别计时
+  let mut phase_start = start_time;
+  // 生成 patch 时使用的选项
+  let patch_options = options.patch_options();
+
+  // 提示可能写错的正则
+  for warning in regex_config.diagnostics() {
+    log_warn!("Regex config: {}", warning);
+  }
+
   // 输出文件的 map
   let mut output_files_map = IndexMap::new();
-  // 加载配置文件（文件夹白名单+正则表达式）
-  let (dir_whitelist, regex_config) = load_config()?;
+  // 输出文件 -> 源文件相对路径（仅合并输出时使用）
+  let mut source_paths = HashMap::new();
+  // 反向 patch 输出文件的 map
+  let mut reverse_files_map = IndexMap::new();
+  // 处理失败的文件及原因
+  let mut failed_files = Vec::new();
+  // 后缀名 -> 生成的 replace 操作数
+  let mut extension_counts: HashMap<String, usize> = HashMap::new();
+  // 没有生成任何操作的文件数
+  let mut empty_file_count = 0;
+  // 字符串 -> 出现位置（源文件相对路径, JSON Pointer）
+  let mut string_locations: IndexMap<String, Vec<(String, String)>> = IndexMap::new();
+  // 基础文件路径 -> 解析结果（不存在或解析失败时为 None）
+  let mut base_cache: HashMap<PathBuf, Option<Value>> = HashMap::new();
+  let numeric_key_policy = options.numeric_key_policy(regex_config);
+  // （后缀名, 正则下标, 正则）-> 该正则生成的操作（源文件相对路径, JSON Pointer）
+  let mut explanations: IndexMap<(String, usize, String), Vec<(String, String)>> = IndexMap::new();
+
+  // 2. 遍历输入目录（单个文件时不遍历）
+  let is_walk = matches!(input_selection, InputSelection::Walk(_));
+  let mut input_files = match input_selection {
+    InputSelection::Walk(dir_whitelist) => {
+      walk_input_dir(input_dir, output_dir, options, dir_whitelist, regex_config)?
+    }
+    InputSelection::File(file_path) => vec![file_path],
+  };
+  let matched_file_count = input_files.len();
+
+  // 增量模式：跳过在 since 之前修改的文件，保留其已生成的输出
+  let last_run_path = output_dir.join(LAST_RUN_FILE);
+  let since = match options.since {
+    Some(since) => Some(since),
+    None if options.since_last => read_last_run(&last_run_path)?,
+    None => None,
+  };
+  if let Some(since) = since {
+    let total = input_files.len();
+    input_files.retain(|file_path| {
+      fs::metadata(file_path)
+        .and_then(|metadata| metadata.modified())
+        .map_or(true, |modified| modified >= since)
+    });
+    log_info!(
+      "Skipped {} file(s) unchanged since {}",
+      total - input_files.len(),
+      timestamp::format_rfc3339(since)
+    );
+  }
+  log_verbose!("{} input file(s) to process", input_files.len());
+  // 记录本次运行的开始时间，运行期间修改的文件下次仍会被处理
+  let run_started_at = SystemTime::now();
+
+  // 并行读取文件
+  let read_results = parallel::par_map(input_files, |file_path| {
+    let result = fs::read_to_string(&file_path).context("Failed to read file");
+    (file_path, result)
+  });
+  let mut input_files_map = IndexMap::new();
+  for (file_path, result) in read_results {
+    match result {
+      Ok(json_str) => {
+        let (ext, is_patch) = get_extension_info(&file_path);
+        input_files_map.insert(file_path, (json_str, ext, is_patch));
+      }
+      Err(e) => record_failure(&mut failed_files, file_path, e, options.strict)?,
+    }
+  }
+
+  log_info!(
+    "Phase read: {} file(s) read - time: {}",
+    input_files_map.len(),
+    format_duration(phase_start.elapsed())
+  );
+  phase_start = Instant::now();
+
+  // 3. 生成 patch
+  // 是否已超出时间限制，超出后不再处理剩余的文件
+  let over_budget = || {
+    options
+      .time_budget
+      .is_some_and(|time_budget| start_time.elapsed() > time_budget)
+  };
+  // 并行解析并生成 patch
+  let generated_patches = parallel::par_map(
+    input_files_map.into_iter().collect(),
+    |(file_path, (json_str, ext, is_patch))| {
+      if over_budget() {
+        return (file_path, None);
+      }
+      let parsed = if options.capture_comments {
+        json5::parse_with_comments(&json_str).map(|(v, c)| (v, Some(c)))
+      } else {
+        json5::parse(&json_str).map(|v| (v, None))
+      };
+      let result = parsed.map(|(json_value, comments)| {
+        let relative_path = file_path.strip_prefix(input_dir).unwrap_or(&file_path);
+        let json_value_vec = json_patch::generate_patch(
+          is_patch,
+          &json_value,
+          &ext,
+          relative_path,
+          regex_config,
+          comments.as_ref().filter(|_| options.annotate),
+          &patch_options,
+        );
+        (ext, is_patch, json_value, json_value_vec, comments)
+      });
+      (file_path, Some(result))
+    },
+  );
+
+  // 因超出时间限制而未处理的文件数
+  let mut over_budget_count = 0;
+  let generated_count = generated_patches.len();
+  for (file_path, result) in generated_patches {
+    // 生成和后处理同样计入时间限制，超出后剩余已生成的文件也不再处理
+    let result = result.filter(|_| !over_budget());
+    let (ext, is_patch, json_value, mut json_value_vec, comments) = match result {
+      Some(Ok(generated_patch)) => generated_patch,
+      None => {
+        over_budget_count += 1;
+        continue;
+      }
+      Some(Err(e)) => {
+        let e = anyhow::Error::new(e).context("Failed to parse file");
+        record_failure(&mut failed_files, file_path, e, options.strict)?;
+        continue;
+      }
+    };
+    // 基础 Mod 中对应的文件，用于决定生成 add 还是 replace
+    let base_value = match &options.base_dir {
+      Some(base_dir) => load_base_file(
+        base_dir,
+        file_path.strip_prefix(input_dir)?,
+        &mut base_cache,
+      ),
+      None => None,
+    };
+    if !post_process_patch(
+      &file_path,
+      &json_value,
+      is_patch,
+      &mut json_value_vec,
+      base_value,
+      numeric_key_policy,
+      options,
+    ) {
+      continue;
+    }
+    if let Some(comments) = &comments
+      && !options.annotate
+    {
+      list_comments(&file_path, &json_value, &json_value_vec, comments);
+    }
+    if json_value_vec.is_empty() {
+      log_verbose!("{} produced no operations", file_path.display());
+      empty_file_count += 1;
+      continue;
+    }
+    // 源文件相对路径，路径分隔符统一为 '/'
+    let relative_path = file_path
+      .strip_prefix(input_dir)?
+      .to_string_lossy()
+      .replace('\\', "/");
+    // 合并输出时在指针前加上源文件路径，避免不同文件的操作路径相同
+    let prefixed_patch = (options.single_file.is_some() && options.relative_pointer).then(|| {
+      let mut prefixed_patch = json_value_vec.clone();
+      prefixed_patch.prefix_paths(&json_patch::path_to_pointer(&relative_path));
+      prefixed_patch
+    });
+    // 检查最终写入的操作格式是否正确
+    if options.self_check
+      && let Err(e) = json_patch::validate_patch(prefixed_patch.as_ref().unwrap_or(&json_value_vec))
+    {
+      let e = e.context("Self-check failed, the generated patch is malformed");
+      record_failure(&mut failed_files, file_path, e, options.strict)?;
+      continue;
+    }
+    // 输出文件名
+    let output_file_path = output_file_path(
+      output_dir,
+      file_path.strip_prefix(input_dir)?,
+      is_patch,
+      options.naming,
+      options.format,
+    );
+    // 替换后缀名时不同源文件可能得到相同的输出文件名
+    if output_files_map.contains_key(&output_file_path) {
+      log_warn!(
+        "{} maps to the same output file as another source, {} will be overwritten.",
+        file_path.display(),
+        output_file_path.display()
+      );
+    }
+    // 检查 patch 能否应用到源文件上（patch文件的路径指向原版资源，无法检查）
+    if options.roundtrip_check && !is_patch {
+      let mut source_copy = json_value.clone();
+      for failure in json_patch::apply_patch(&mut source_copy, &json_value_vec) {
+        log_warn!(
+          "Roundtrip check failed for {}: {}",
+          file_path.display(),
+          failure
+        );
+      }
+    }
+    // 生成反向 patch，与正向 patch 使用相同的相对路径
+    if let Some(reverse_dir) = &options.reverse_dir {
+      let reverse_file_path = reverse_dir.join(output_file_path.strip_prefix(output_dir)?);
+      let source = if is_patch { None } else { Some(&json_value) };
+      let marker = regex_config.resolve_marker(&ext, file_path.strip_prefix(input_dir)?);
+      let mut reverse_patch = json_patch::generate_reverse_patch(&json_value_vec, source, marker);
+      if options.sort_keys {
+        reverse_patch.sort_keys();
+      }
+      reverse_files_map.insert(reverse_file_path, reverse_patch);
+    }
+    // 记录字符串出现位置
+    if options.dedupe_across_files || options.sample.is_some() {
+      let relative_path = file_path.strip_prefix(input_dir)?.to_string_lossy();
+      collect_string_locations(&json_value_vec, &relative_path, &mut string_locations);
+    }
+    // 记录每个操作由哪个正则生成
+    if options.explain
+      && let Some(pattern_set) = regex_config.get_pattern_set(&ext)
+    {
+      let relative_path = file_path.strip_prefix(input_dir)?.to_string_lossy();
+      for patch_operation in json_value_vec.operations() {
+        if patch_operation["op"] == "test" {
+          continue;
+        }
+        let path = patch_operation["path"].as_str().unwrap_or_default();
+        if let Some((index, pattern)) = pattern_set.explain(path) {
+          // patch文件的路径指向原版资源，无法区分纯数字的对象键和数组下标
+          let human_path = if is_patch {
+            json_patch::pointer_to_human(path)
+          } else {
+            json_patch::pointer_to_human_in(&json_value, path)
+          };
+          explanations
+            .entry((ext.clone(), index, pattern.to_string()))
+            .or_default()
+            .push((relative_path.to_string(), human_path));
+        }
+      }
+    }
+    // 统计各后缀名的 replace 操作数
+    *extension_counts.entry(ext).or_insert(0) += json_value_vec.operation_count();
+    if options.single_file.is_some() {
+      source_paths.insert(output_file_path.clone(), relative_path);
+    }
+    // 写入到用于输出文件的map中
+    output_files_map.insert(output_file_path, prefixed_patch.unwrap_or(json_value_vec));
+  }
+
+  // 按路径排序，保证输出顺序稳定
+  output_files_map.sort_keys();
+  let output_file_count = output_files_map.len();
+  reverse_files_map.sort_keys();
+
+  log_info!(
+    "Phase parse+generate: {} operation(s) generated - time: {}",
+    output_files_map
+      .values()
+      .map(PatchData::operation_count)
+      .sum::<usize>(),
+    format_duration(phase_start.elapsed())
+  );
+  phase_start = Instant::now();
+
+  // 4. 输出 patch 到目录（dry run 时只输出汇总）
+  // 写入前输出与已有文件的 diff（合并为单个文件时不输出）
+  if options.show_diff && options.single_file.is_none() {
+    for (output_file_path, json_value_vec) in &output_files_map {
+      print_file_diff(
+        output_file_path,
+        &patch_to_string(json_value_vec, options.format, options.indent)?,
+      );
+    }
+  }
+  let mut bytes_written = 0;
+  if options.dry_run {
+    print_dry_run_summary(&output_files_map);
+  } else {
+    match &options.single_file {
+      Some(single_file_path) => {
+        bytes_written += write_single_file(
+          single_file_path,
+          &output_files_map,
+          &source_paths,
+          options.format,
+          options.indent,
+        )?
+      }
+      None => {
+        for (output_file_path, json_value_vec) in &output_files_map {
+          bytes_written += write_patch_file(
+            output_file_path,
+            json_value_vec,
+            options.format,
+            options.indent,
+          )?;
+        }
+      }
+    }
+    for (reverse_file_path, json_value_vec) in &reverse_files_map {
+      bytes_written += write_patch_file(
+        reverse_file_path,
+        json_value_vec,
+        options.format,
+        options.indent,
+      )?;
+    }
+  }
+
+  // 5. 随机抽样字符串
+  if let Some(sample_size) = options.sample {
+    print_sample(&string_locations, sample_size, options.sample_seed);
+  }
+
+  // 6. 输出跨文件重复字符串表
+  if options.dedupe_across_files && !options.dry_run {
+    write_string_table(output_dir, string_locations)?;
+  }
+
+  // 更新上次运行时间（未处理完所有文件时不更新，下次仍处理剩余的文件）
+  if options.since_last && !options.dry_run && over_budget_count == 0 {
+    fs::create_dir_all(output_dir)?;
+    fs::write(&last_run_path, timestamp::format_rfc3339(run_started_at)).context(format!(
+      "[ERROR] Failed to write {}",
+      last_run_path.display()
+    ))?;
+  }
+
+  log_info!(
+    "Phase write: {} byte(s) written - time: {}",
+    bytes_written,
+    format_duration(phase_start.elapsed())
+  );
+  log_info!("Total time: {}", format_duration(start_time.elapsed()));
+
+  // 7. 输出各后缀名的统计
+  if options.explain {
+    print_explanations(explanations);
+  }
+  print_extension_summary(&extension_counts, empty_file_count);
+
+  // 写入运行报告（有文件处理失败时也写入）
+  if let Some(report_path) = &options.report {
+    let report = json!({
+      "files_scanned": if is_walk { count_files(input_dir, options) } else { 1 },
+      "files_matched": matched_file_count,
+      "files_with_output": output_file_count,
+      "operations": output_files_map
+        .values()
+        .map(PatchData::operation_count)
+        .sum::<usize>(),
+      "batches": output_files_map
+        .values()
+        .map(PatchData::batch_count)
+        .sum::<usize>(),
+      "operations_per_extension": extension_counts
+        .iter()
+        .collect::<BTreeMap<_, _>>(),
+      "errors": failed_files
+        .iter()
+        .map(|(file_path, e)| json!({
+          "file": file_path.to_string_lossy(),
+          "message": format!("{:#}", e),
+        }))
+        .collect::<Vec<_>>(),
+      "files_over_budget": over_budget_count,
+      "elapsed_ms": start_time.elapsed().as_millis() as u64,
+    });
+    write_report(report_path, &report)?;
+    log_info!("Report written to {}", report_path.display());
+  }
 
-  // 2. 遍历输入目录
-  for entry in WalkDir::new(input_dir)
+  // 超出时间限制时已写入生成的部分，最后仍以失败退出
+  if over_budget_count > 0 {
+    log_warn!(
+      "Time budget of {} exceeded: {} file(s) processed, {} file(s) remaining",
+      format_duration(options.time_budget.unwrap_or_default()),
+      generated_count - over_budget_count,
+      over_budget_count
+    );
+  }
+
+  // 8. 汇总处理失败的文件
+  if !failed_files.is_empty() {
+    println!("[ERROR] {} file(s) failed:", failed_files.len());
+    for (file_path, e) in &failed_files {
+      println!("  {}: {:#}", file_path.display(), e);
+    }
+    if !options.ignore_errors {
+      bail!("[ERROR] {} file(s) failed to process!", failed_files.len());
+    }
+  }
+  if over_budget_count > 0 {
+    bail!(
+      "[ERROR] Time budget exceeded, {} file(s) were not processed!",
+      over_budget_count
+    );
+  }
+
+  Ok(())
+}
+
+/// 遍历输入目录时的选项
+#[derive(Debug, Default)]
+pub(crate) struct WalkOptions<'a> {
+  /// 跟随符号链接
+  pub follow_symlinks: bool,
+  /// 排除相对路径匹配这些 glob 的文件或目录
+  pub exclude: &'a [String],
+  /// 跳过大于该字节数的文件，0 表示不限制
+  pub max_file_size: u64,
+  /// 遍历隐藏（名称以 '.' 开头）的文件和目录
+  pub include_hidden: bool,
+}
+
+/// 遍历输入目录，返回白名单目录内、后缀名在正则配置中的文件
+pub(crate) fn collect_input_files(
+  input_dir: &Path,
+  dir_whitelist: &HashSet<String>,
+  regex_config: &PatternConfig,
+  walk_options: &WalkOptions,
+) -> Result<Vec<PathBuf>> {
+  // 遍历前编译白名单和排除列表
+  let dir_whitelist =
+    DirWhitelist::new(dir_whitelist).context("[ERROR] Invalid pattern in dir whitelist!")?;
+  let exclude =
+    ExcludeGlobs::new(walk_options.exclude).context("[ERROR] Invalid exclude pattern!")?;
+
+  let input_files = WalkDir::new(input_dir)
+    .sort_by_file_name() // 固定遍历顺序，保证抽样等结果可复现
+    .follow_links(walk_options.follow_symlinks)
     .into_iter()
-    .filter_map(|e| e.ok()) // 过滤掉错误项
+    // 默认不进入隐藏目录（如 .git）
+    .filter_entry(|e| walk_options.include_hidden || !is_hidden(e))
+    .filter_map(|e| match e {
+      Ok(entry) => Some(entry),
+      // 跳过出错的项（如符号链接循环）
+      Err(e) => {
+        log_warn!("Skipped {}: {}", e.path().unwrap_or(input_dir).display(), e);
+        None
+      }
+    })
     .filter(|e| {
       // 过滤掉非文件项
       if !e.file_type().is_file() {
@@ -56,120 +1212,560 @@ pub fn run(input: PathBuf, output: PathBuf, test: bool) -> Result<()> {
       // 过滤掉非白名单内的子目录
       let file_path = e.path();
       let relative_path = file_path.strip_prefix(input_dir).unwrap();
-      if !dir_whitelist
-        .iter()
-        .any(|dir| relative_path.starts_with(dir))
-      {
+      if !dir_whitelist.is_match(relative_path) {
+        return false;
+      }
+      // 过滤掉被排除的文件
+      if exclude.is_match(relative_path) {
         return false;
       }
       // 过滤掉非白名单内的文件后缀名
       let (ext, _) = get_extension_info(file_path);
-      regex_config.contains_extension(&ext)
+      if !regex_config.contains_extension(&ext) {
+        return false;
+      }
+      // 过滤掉过大的文件
+      if walk_options.max_file_size > 0
+        && let Ok(metadata) = e.metadata()
+        && metadata.len() > walk_options.max_file_size
+      {
+        log_warn!(
+          "Skipped {}: {} bytes exceeds the maximum file size of {} bytes",
+          file_path.display(),
+          metadata.len(),
+          walk_options.max_file_size
+        );
+        return false;
+      }
+      true
     })
+    .map(|e| e.into_path())
+    .collect();
+
+  Ok(input_files)
+}
+
+/// 检查是否为隐藏（名称以 '.' 开头）的文件或目录，输入目录本身除外
+fn is_hidden(entry: &DirEntry) -> bool {
+  entry.depth() > 0 && entry.file_name().to_string_lossy().starts_with('.')
+}
+
+/// 统计输入目录中的文件总数（不考虑白名单），与收集输入文件时一样跳过隐藏的文件和目录
+fn count_files(input_dir: &Path, options: &GenOptions) -> usize {
+  WalkDir::new(input_dir)
+    .follow_links(options.follow_symlinks)
+    .into_iter()
+    .filter_entry(|e| options.include_hidden || !is_hidden(e))
+    .filter_map(|e| e.ok())
+    .filter(|e| e.file_type().is_file())
+    .count()
+}
+
+/// 写入 JSON 运行报告
+fn write_report(report_path: &Path, report: &Value) -> Result<()> {
+  if let Some(parent) = report_path.parent()
+    && !parent.as_os_str().is_empty()
   {
-    let file_path = entry.path();
-    let (ext, is_patch) = get_extension_info(file_path);
-    let json_str = fs::read_to_string(file_path)?;
-    input_files_map.insert(file_path.to_path_buf(), (json_str, ext, is_patch));
+    fs::create_dir_all(parent)?;
   }
+  fs::write(report_path, serde_json::to_string_pretty(report)?).context(format!(
+    "[ERROR] Failed to write report {}",
+    report_path.display()
+  ))
+}
 
-  let duration = start_time.elapsed();
+/// 读取上次运行时间，文件不存在时返回 None
+fn read_last_run(last_run_path: &Path) -> Result<Option<SystemTime>> {
+  if !last_run_path.exists() {
+    log_info!("No previous run recorded, processing all files");
+    return Ok(None);
+  }
+  let content = fs::read_to_string(last_run_path).context(format!(
+    "[ERROR] Failed to read {}",
+    last_run_path.display()
+  ))?;
+  let since = timestamp::parse_rfc3339(&content).context(format!(
+    "[ERROR] Invalid timestamp in {}",
+    last_run_path.display()
+  ))?;
+  Ok(Some(since))
+}
+
+/// 未使用 --annotate 时，在 -v 下列出 patch 操作对应的注释
+fn list_comments(
+  file_path: &Path,
+  json_value: &Value,
+  json_value_vec: &PatchData,
+  comments: &json_patch::Comments,
+) {
+  for patch_operation in json_value_vec.operations() {
+    if let Some(json_pointer) = patch_operation["path"].as_str()
+      && let Some(comment) = comments.get(json_pointer)
+    {
+      log_verbose!(
+        "{}:{} // {}",
+        file_path.display(),
+        json_patch::pointer_to_human_in(json_value, json_pointer),
+        comment
+      );
+    }
+  }
+}
+
+/// 按正则分组输出其生成的操作，操作的路径已转换为便于阅读的形式
+fn print_explanations(mut explanations: IndexMap<(String, usize, String), Vec<(String, String)>>) {
+  explanations.sort_keys();
+  log_info!("Operations by matching pattern:");
+  for ((ext, index, pattern), operations) in &explanations {
+    println!(
+      "  {} #{} {} ({} operations)",
+      ext,
+      index,
+      pattern,
+      operations.len()
+    );
+    for (file, human_path) in operations {
+      println!("    {}:{}", file, human_path);
+    }
+  }
+}
+
+/// 输出各后缀名生成的字符串数量
+fn print_extension_summary(extension_counts: &HashMap<String, usize>, empty_file_count: usize) {
+  if !log::enabled(LogLevel::Normal) {
+    return;
+  }
+  let mut extensions: Vec<(&String, &usize)> = extension_counts.iter().collect();
+  extensions.sort();
+  let width = extensions
+    .iter()
+    .map(|(ext, _)| ext.len())
+    .max()
+    .unwrap_or(0)
+    .max("Total".len());
+
+  log_info!("Translatable strings per extension:");
+  for (ext, count) in &extensions {
+    println!("  {:<width$}  {}", ext, count);
+  }
   println!(
-    "[INFO] Files reading completed - time elapsed: {}.{:03}s",
-    duration.as_secs(),
-    duration.subsec_millis()
+    "  {:<width$}  {}",
+    "Total",
+    extension_counts.values().sum::<usize>()
   );
+  log_info!("{} file(s) produced no operations", empty_file_count);
+}
 
-  // 3. 生成 patch
-  for (file_path, (json_str, ext, is_patch)) in input_files_map {
-    let json_value = json5::parse(&json_str)?;
-    // 生成 patch
-    let json_value_vec =
-      json_patch::generate_patch(is_patch, &json_value, &ext, &regex_config, gen_test);
-    if json_value_vec.is_empty() {
-      continue;
+/// 源文件对应的输出文件路径：patch 文件保持原名，其他文件按命名方式追加或替换为 ".patch"，
+/// 再追加输出格式对应的后缀
+pub(crate) fn output_file_path(
+  output_dir: &Path,
+  relative_path: &Path,
+  is_patch: bool,
+  naming: PatchNaming,
+  format: OutputFormat,
+) -> PathBuf {
+  let patch_path = match (is_patch, naming) {
+    (true, _) => relative_path.to_path_buf(),
+    (false, PatchNaming::Append) => {
+      PathBuf::from(format!("{}.patch", relative_path.to_string_lossy()))
     }
-    // 输出文件名
-    let output_file_path = if is_patch {
-      PathBuf::from(output_dir).join(file_path.strip_prefix(input_dir)?)
-    } else {
-      PathBuf::from(output_dir).join(format!(
-        "{}.patch",
-        file_path.strip_prefix(input_dir)?.to_string_lossy()
-      ))
-    };
-    // 写入到用于输出文件的map中
-    output_files_map.insert(output_file_path, json_value_vec);
+    (false, PatchNaming::Replace) => relative_path.with_extension("patch"),
+  };
+  output_dir.join(format!(
+    "{}{}",
+    patch_path.to_string_lossy(),
+    format.suffix()
+  ))
+}
+
+/// 写入单个 patch 文件，返回写入的字节数
+pub(crate) fn write_patch_file(
+  output_file_path: &Path,
+  json_value_vec: &PatchData,
+  format: OutputFormat,
+  indent: Indent,
+) -> Result<usize> {
+  fs::create_dir_all(
+    output_file_path
+      .parent()
+      .context("[ERROR] Failed to get parent directory!")?,
+  )?;
+
+  let patch_str = patch_to_string(json_value_vec, format, indent)?;
+  fs::write(output_file_path, &patch_str)?;
+
+  Ok(patch_str.len())
+}
+
+/// 输出已有的输出文件与新生成内容的 unified diff，文件不存在或内容相同时不输出
+fn print_file_diff(output_file_path: &Path, patch_str: &str) {
+  if !output_file_path.exists() {
+    return;
+  }
+  let existing = match fs::read_to_string(output_file_path) {
+    Ok(existing) => existing,
+    Err(e) => {
+      log_warn!(
+        "Failed to read {} for diff: {}",
+        output_file_path.display(),
+        e
+      );
+      return;
+    }
+  };
+  let label = output_file_path.display();
+  let diff = text_diff::unified_diff(
+    &existing,
+    patch_str,
+    &format!("{} (existing)", label),
+    &format!("{} (generated)", label),
+    3,
+  );
+  if !diff.is_empty() {
+    print!("{}", diff);
   }
+}
 
-  let duration = start_time.elapsed();
-  println!(
-    "[INFO] Patches generation completed - time elapsed: {}.{:03}s",
-    duration.as_secs(),
-    duration.subsec_millis()
+/// 序列化 patch
+fn patch_to_string(
+  json_value_vec: &PatchData,
+  format: OutputFormat,
+  indent: Indent,
+) -> Result<String> {
+  let patch_str = match (format, json_value_vec) {
+    (OutputFormat::Json, PatchData::CommonPatch(values)) => to_string_indented(values, indent)?,
+    (OutputFormat::Json, PatchData::BatchesPatch(values)) => to_string_indented(values, indent)?,
+    (OutputFormat::Yaml, PatchData::CommonPatch(values)) => yaml::to_string(&json!(values)),
+    (OutputFormat::Yaml, PatchData::BatchesPatch(values)) => yaml::to_string(&json!(values)),
+  };
+  Ok(patch_str)
+}
+
+/// 使用指定缩进格式化 JSON（YAML 输出不受影响）
+fn to_string_indented<T: Serialize>(value: &T, indent: Indent) -> Result<String> {
+  let indent_str = indent.as_string();
+  let mut buffer = Vec::new();
+  let formatter = PrettyFormatter::with_indent(indent_str.as_bytes());
+  let mut serializer = Serializer::with_formatter(&mut buffer, formatter);
+  value.serialize(&mut serializer)?;
+  Ok(String::from_utf8(buffer)?)
+}
+
+/// 把所有 patch 合并写入一个文件，以源文件相对路径为键，按键排序，返回写入的字节数
+fn write_single_file(
+  single_file_path: &Path,
+  output_files_map: &IndexMap<PathBuf, PatchData>,
+  source_paths: &HashMap<PathBuf, String>,
+  format: OutputFormat,
+  indent: Indent,
+) -> Result<usize> {
+  let mut entries: Vec<(String, Value)> = output_files_map
+    .iter()
+    .map(|(output_file_path, json_value_vec)| {
+      let patch_value = match json_value_vec {
+        PatchData::CommonPatch(values) => json!(values),
+        PatchData::BatchesPatch(values) => json!(values),
+      };
+      (source_paths[output_file_path].clone(), patch_value)
+    })
+    .collect();
+  entries.sort_by(|a, b| a.0.cmp(&b.0));
+  let merged: serde_json::Map<String, Value> = entries.into_iter().collect();
+
+  if let Some(parent) = single_file_path.parent()
+    && !parent.as_os_str().is_empty()
+  {
+    fs::create_dir_all(parent)?;
+  }
+  let merged_str = match format {
+    OutputFormat::Json => to_string_indented(&merged, indent)?,
+    OutputFormat::Yaml => yaml::to_string(&Value::Object(merged.clone())),
+  };
+  fs::write(single_file_path, &merged_str).context(format!(
+    "[ERROR] Failed to write merged patch file to {}",
+    single_file_path.display()
+  ))?;
+  log_info!(
+    "Merged {} patch(es) into {}",
+    merged.len(),
+    single_file_path.display()
   );
 
-  // 4. 输出 patch 到目录
-  for (output_file_path, json_value_vec) in output_files_map {
-    fs::create_dir_all(
-      output_file_path
-        .parent()
-        .context("[ERROR] Failed to get parent directory!")?,
-    )?;
+  Ok(merged_str.len())
+}
 
+/// 把耗时格式化为 "秒.毫秒s"
+fn format_duration(duration: Duration) -> String {
+  format!("{}.{:03}s", duration.as_secs(), duration.subsec_millis())
+}
+
+/// 输出 dry run 时每个文件将写入的操作数及总数
+fn print_dry_run_summary(output_files_map: &IndexMap<PathBuf, PatchData>) {
+  let (mut total_operations, mut total_batches) = (0, 0);
+  for (output_file_path, json_value_vec) in output_files_map {
+    let (operation_count, batch_count) = (
+      json_value_vec.operation_count(),
+      json_value_vec.batch_count(),
+    );
+    total_operations += operation_count;
+    total_batches += batch_count;
     match json_value_vec {
-      PatchData::CommonPatch(values) => {
-        fs::write(output_file_path, serde_json::to_string_pretty(&values)?)?
+      PatchData::CommonPatch(_) => {
+        println!(
+          "{} -> {} operations",
+          output_file_path.display(),
+          operation_count
+        );
       }
-      PatchData::BatchesPatch(values) => {
-        fs::write(output_file_path, serde_json::to_string_pretty(&values)?)?
+      PatchData::BatchesPatch(_) => {
+        println!(
+          "{} -> {} batches ({} operations)",
+          output_file_path.display(),
+          batch_count,
+          operation_count
+        );
       }
     }
   }
+  log_info!(
+    "Dry run: {} file(s) would be written ({} operations, {} batches)",
+    output_files_map.len(),
+    total_operations,
+    total_batches
+  );
+}
 
-  let duration = start_time.elapsed();
-  println!(
-    "[INFO] Patches writing completed - total time: {}.{:03}s",
-    duration.as_secs(),
-    duration.subsec_millis()
+/// 记录处理失败的文件，严格模式下直接返回错误
+fn record_failure(
+  failed_files: &mut Vec<(PathBuf, anyhow::Error)>,
+  file_path: PathBuf,
+  e: anyhow::Error,
+  strict: bool,
+) -> Result<()> {
+  if strict {
+    return Err(e.context(format!("[ERROR] {}", file_path.display())));
+  }
+  failed_files.push((file_path, e));
+  Ok(())
+}
+
+/// 记录 patch 中每个字符串值的出现位置
+fn collect_string_locations(
+  patch_data: &PatchData,
+  relative_path: &str,
+  string_locations: &mut IndexMap<String, Vec<(String, String)>>,
+) {
+  for patch_operation in patch_data.operations() {
+    if patch_operation["op"] == "test" {
+      continue;
+    }
+    let path = patch_operation["path"].as_str().unwrap_or_default();
+    let mut record = |value: &str, pointer: String| {
+      string_locations
+        .entry(value.to_string())
+        .or_default()
+        .push((relative_path.to_string(), pointer));
+    };
+    match &patch_operation["value"] {
+      Value::String(value) => record(value, path.to_string()),
+      Value::Array(values) => {
+        for (index, value) in values.iter().enumerate() {
+          if let Value::String(value) = value {
+            record(value, format!("{}/{}", path, index));
+          }
+        }
+      }
+      _ => {}
+    }
+  }
+}
+
+/// 随机抽取字符串，并输出其所在文件和路径
+fn print_sample(
+  string_locations: &IndexMap<String, Vec<(String, String)>>,
+  sample_size: usize,
+  seed: Option<u64>,
+) {
+  let seed = seed.unwrap_or_else(|| {
+    SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map(|d| d.as_nanos() as u64)
+      .unwrap_or_default()
+  });
+  let all_strings: Vec<(&String, &String, &String)> = string_locations
+    .iter()
+    .flat_map(|(value, locations)| {
+      locations
+        .iter()
+        .map(move |(file, pointer)| (file, pointer, value))
+    })
+    .collect();
+  let total = all_strings.len();
+  let sampled = random::sample(all_strings, sample_size, seed);
+
+  log_info!(
+    "Sampled {} of {} string(s) (seed: {})",
+    sampled.len(),
+    total,
+    seed
   );
+  for (file, pointer, value) in sampled {
+    println!(
+      "{}:{} {}",
+      file,
+      json_patch::pointer_to_human(pointer),
+      serde_json::to_string(value).unwrap()
+    );
+  }
+}
+
+/// 输出出现在多个文件中的字符串及其位置
+fn write_string_table(
+  output_dir: &Path,
+  string_locations: IndexMap<String, Vec<(String, String)>>,
+) -> Result<()> {
+  let mut string_table = serde_json::Map::new();
+  for (value, locations) in string_locations {
+    let files: HashSet<&String> = locations.iter().map(|(file, _)| file).collect();
+    if files.len() < 2 {
+      continue;
+    }
+    let locations: Vec<Value> = locations
+      .iter()
+      .map(|(file, pointer)| json!({ "file": file, "path": pointer }))
+      .collect();
+    string_table.insert(value, Value::Array(locations));
+  }
+
+  log_info!(
+    "Found {} string(s) duplicated across files",
+    string_table.len()
+  );
+  fs::create_dir_all(output_dir)?;
+  let table_path = output_dir.join("string_table.json");
+  fs::write(&table_path, serde_json::to_string_pretty(&string_table)?).context(format!(
+    "[ERROR] Failed to write string table to {}",
+    table_path.display()
+  ))?;
 
   Ok(())
 }
 
-/// 加载配置
-fn load_config() -> Result<(HashSet<String>, PatternConfig)> {
-  // 尝试从可执行文件目录加载，如果有任何一步失败，直接使用默认配置
-  let exe_dir = std::env::current_exe();
+/// 项目配置文件名，同时包含文件夹白名单和正则配置
+const PROJECT_CONFIG_FILE: &str = "neki_config.json";
+/// 项目配置目录名，包含 dirs_config.json 和 regex_config.json
+const PROJECT_CONFIG_DIR: &str = ".neki";
 
-  let (dirs_str, dirs_source);
-  let (regex_str, regex_source);
+/// 合并的项目配置，缺少的部分使用内置配置
+#[derive(Debug, Deserialize)]
+struct ProjectConfig {
+  #[serde(default)]
+  dirs: Option<HashSet<String>>,
+  #[serde(default)]
+  regex: Option<RawPatternConfig>,
+}
 
-  // 如果可执行文件目录存在，则尝试从该目录加载配置
-  // 如果可执行文件目录不存在或出现其他问题，则使用默认配置
-  match exe_dir {
-    Ok(o) => match o.parent() {
-      Some(parent) => {
-        (dirs_str, dirs_source) = read_config_file(
-          Path::new(parent).join("dirs_config.json").as_path(),
-          DEFAULT_DIR_CONFIG,
-        )?;
-        (regex_str, regex_source) = read_config_file(
-          Path::new(parent).join("regex_config.json").as_path(),
-          DEFAULT_REGEX_CONFIG,
-        )?;
-      }
+/// 加载配置，并输出配置来源
+pub(crate) fn load_config(
+  config_dir: Option<&Path>,
+  input_dir: Option<&Path>,
+) -> Result<(HashSet<String>, PatternConfig)> {
+  let (dirs, patterns, source) = read_config(config_dir, input_dir)?;
+  log_info!("{}", source);
+  Ok((dirs, patterns))
+}
+
+/// 加载配置，同时返回配置来源的说明
+/// 优先级：config_dir > 从 input_dir 向上查找的项目配置 > 可执行文件目录 > 内置配置
+pub(crate) fn read_config(
+  config_dir: Option<&Path>,
+  input_dir: Option<&Path>,
+) -> Result<(HashSet<String>, PatternConfig, String)> {
+  // 未指定配置目录时，先查找项目配置
+  let project_config = match config_dir {
+    Some(_) => None,
+    None => input_dir.and_then(find_project_config),
+  };
+
+  let (dirs, patterns, source) = match project_config {
+    // 合并的项目配置文件
+    Some(path) if path.is_file() => {
+      let config_str = fs::read_to_string(&path).context(format!(
+        "[ERROR] Failed to read project config {}",
+        path.display()
+      ))?;
+      let project_config = json5::from_str::<ProjectConfig>(&config_str).context(format!(
+        "[ERROR] Failed to parse project config {}",
+        path.display()
+      ))?;
+      let dirs = match project_config.dirs {
+        Some(dirs) => dirs,
+        None => json5::from_str(DEFAULT_DIR_CONFIG)?,
+      };
+      let patterns = match project_config.regex {
+        Some(patterns) => PatternConfig::from_raw_config(patterns)?,
+        None => PatternConfig::from_json5_str(DEFAULT_REGEX_CONFIG)?,
+      };
+      let source = format!("Using project configurations from {}", path.display());
+      (dirs, patterns, source)
+    }
+    // 项目配置目录，与 config_dir 的处理方式相同
+    Some(path) => load_config_dir(Some(path.as_path()))?,
+    None => match config_dir {
+      Some(dir) => load_config_dir(Some(dir))?,
+      // 尝试从可执行文件目录加载，如果有任何一步失败，直接使用默认配置
       None => {
-        (dirs_str, dirs_source) = (DEFAULT_DIR_CONFIG.to_owned(), ConfigSource::BuiltIn);
-        (regex_str, regex_source) = (DEFAULT_REGEX_CONFIG.to_owned(), ConfigSource::BuiltIn);
+        let exe_dir = std::env::current_exe()
+          .ok()
+          .and_then(|exe| exe.parent().map(Path::to_path_buf));
+        load_config_dir(exe_dir.as_deref())?
       }
     },
-    Err(_) => {
+  };
+
+  Ok((dirs, patterns, source))
+}
+
+/// 从 input_dir 开始逐级向上查找项目配置文件或配置目录
+fn find_project_config(input_dir: &Path) -> Option<PathBuf> {
+  let input_dir = input_dir.canonicalize().ok()?;
+  input_dir.ancestors().find_map(|dir| {
+    let config_file = dir.join(PROJECT_CONFIG_FILE);
+    if config_file.is_file() {
+      return Some(config_file);
+    }
+    let config_dir = dir.join(PROJECT_CONFIG_DIR);
+    config_dir.is_dir().then_some(config_dir)
+  })
+}
+
+/// 从配置目录加载并解析文件夹白名单和正则配置
+/// 目录中不存在的配置文件使用内置配置
+fn load_config_dir(config_dir: Option<&Path>) -> Result<(HashSet<String>, PatternConfig, String)> {
+  let (dirs_str, dirs_source);
+  let (regex_str, regex_source);
+
+  // 如果配置目录存在，则尝试从该目录加载配置
+  // 如果配置目录不存在或出现其他问题，则使用默认配置
+  match config_dir {
+    Some(parent) => {
+      (dirs_str, dirs_source) = read_config_file(
+        parent.join("dirs_config.json").as_path(),
+        DEFAULT_DIR_CONFIG,
+      )?;
+      (regex_str, regex_source) = read_config_file(
+        parent.join("regex_config.json").as_path(),
+        DEFAULT_REGEX_CONFIG,
+      )?;
+    }
+    None => {
       (dirs_str, dirs_source) = (DEFAULT_DIR_CONFIG.to_owned(), ConfigSource::BuiltIn);
       (regex_str, regex_source) = (DEFAULT_REGEX_CONFIG.to_owned(), ConfigSource::BuiltIn);
     }
   }
 
+  let is_external = dirs_source == ConfigSource::External || regex_source == ConfigSource::External;
   let config_msg = match (dirs_source, regex_source) {
     (ConfigSource::BuiltIn, ConfigSource::BuiltIn) => "Using built-in configurations",
     (ConfigSource::External, ConfigSource::External) => "Using external configurations",
@@ -180,20 +1776,19 @@ fn load_config() -> Result<(HashSet<String>, PatternConfig)> {
       "Using external dir whitelist and built-in regex config"
     }
   };
-  println!("[INFO] {}", config_msg);
+  let source = match config_dir {
+    Some(dir) if is_external => format!("{} from {}", config_msg, dir.display()),
+    _ => config_msg.to_string(),
+  };
 
   // 解析文件夹白名单
-  let dirs_value =
-    json5::parse(&dirs_str).context("[ERROR] Failed to parse dir whitelist config!")?;
-  let dirs = serde_json::from_value::<HashSet<String>>(dirs_value)
-    .context("[ERROR] Failed to deserialize dir whitelist!")?;
+  let dirs = json5::from_str::<HashSet<String>>(&dirs_str)
+    .context("[ERROR] Failed to parse dir whitelist config!")?;
   // 解析正则表达式配置
-  let patterns_value = json5::parse(&regex_str).context("Failed to parse regex config!")?;
-  let patterns = serde_json::from_value::<RawPatternConfig>(patterns_value)
-    .context("[ERROR] Failed to deserialize regex config!")?;
-  let patterns_regex = PatternConfig::from_raw_config(patterns)?;
+  let patterns =
+    PatternConfig::from_json5_str(&regex_str).context("[ERROR] Failed to parse regex config!")?;
 
-  Ok((dirs, patterns_regex))
+  Ok((dirs, patterns, source))
 }
 
 /// 读取配置文件内容，返回内容和来源
@@ -209,25 +1804,475 @@ fn read_config_file(path: &Path, default: &str) -> Result<(String, ConfigSource)
 }
 
 /// 获取文件扩展名信息
-fn get_extension_info(file_path: &Path) -> (String, bool) {
-  // 无后缀名时返回空字符串
+pub(crate) fn get_extension_info(file_path: &Path) -> (String, bool) {
+  // 无后缀名时返回空字符串，后缀名统一转为小写
   let mut file_extension = file_path
     .extension()
     .and_then(|s| s.to_str())
     .unwrap_or("")
-    .to_string();
+    .to_lowercase();
 
   // 特殊处理patch文件
   let is_patch = file_extension == "patch";
   // 如果是patch文件，则获取上一级后缀名，并拼接成完整后缀名
-  if is_patch {
-    if let Some(file_stem) = file_path.file_stem().and_then(|s| s.to_str()) {
-      if let Some(char_index) = file_stem.rfind('.') {
-        // 拼接成完整后缀名，如 example.patch
-        file_extension = format!("{}.{file_extension}", &file_stem[char_index + 1..]);
+  if is_patch
+    && let Some(file_stem) = file_path.file_stem().and_then(|s| s.to_str())
+    && let Some(char_index) = file_stem.rfind('.')
+  {
+    // 拼接成完整后缀名，如 example.patch
+    file_extension = format!(
+      "{}.{file_extension}",
+      file_stem[char_index + 1..].to_lowercase()
+    );
+  }
+
+  (file_extension, is_patch)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::util::zip::tests::{TestEntry, archive_bytes};
+
+  /// 为测试创建空的临时目录
+  fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("neki_lang-test-{}-{}", name, std::process::id()));
+    if dir.exists() {
+      fs::remove_dir_all(&dir).unwrap();
+    }
+    fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  /// 写入文件，自动创建上级目录
+  fn write(path: &Path, contents: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+  }
+
+  /// 在 root 下创建只翻译顶层小写键的配置目录，返回使用该配置的选项
+  fn options_with_config(root: &Path) -> GenOptions {
+    let config_dir = root.join("config");
+    write(
+      &config_dir.join("regex_config.json"),
+      r#"{"codex": ["^/[a-z]+$"]}"#,
+    );
+    GenOptions {
+      config_dir: Some(config_dir),
+      ..GenOptions::default()
+    }
+  }
+
+  /// 读取生成的 patch 中所有操作的 path
+  fn patch_paths(path: &Path) -> Vec<String> {
+    let patch: Value = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+    patch
+      .as_array()
+      .unwrap()
+      .iter()
+      .map(|x| x["path"].as_str().unwrap().to_string())
+      .collect()
+  }
+
+  #[test]
+  fn project_config_shadows_the_built_in_config() {
+    let root = temp_dir("project-config");
+    let input_dir = root.join("mod");
+    fs::create_dir_all(input_dir.join("codex")).unwrap();
+    write(
+      &root.join(PROJECT_CONFIG_FILE),
+      r#"{"regex": {"codex": ["^/custom_key$"]}}"#,
+    );
+
+    let (dirs, patterns, source) = read_config(None, Some(&input_dir)).unwrap();
+    assert!(source.contains("project"), "{}", source);
+    assert_eq!(patterns.extensions().collect::<Vec<_>>(), ["codex"]);
+    assert!(
+      patterns
+        .get_pattern_set("codex")
+        .unwrap()
+        .is_match("/custom_key")
+    );
+    // 项目配置中没有的部分使用内置配置
+    assert_eq!(
+      dirs,
+      json5::from_str::<HashSet<String>>(DEFAULT_DIR_CONFIG).unwrap()
+    );
+
+    // 指定了配置目录时不查找项目配置
+    let options = options_with_config(&root);
+    let (_, patterns, _) = read_config(options.config_dir.as_deref(), Some(&input_dir)).unwrap();
+    assert!(
+      !patterns
+        .get_pattern_set("codex")
+        .unwrap()
+        .is_match("/custom_key")
+    );
+
+    // 项目配置目录同样会被找到
+    fs::remove_file(root.join(PROJECT_CONFIG_FILE)).unwrap();
+    write(
+      &root.join(PROJECT_CONFIG_DIR).join("regex_config.json"),
+      r#"{"codex": ["^/other$"]}"#,
+    );
+    let (_, patterns, _) = read_config(None, Some(&input_dir.join("codex"))).unwrap();
+    assert!(
+      patterns
+        .get_pattern_set("codex")
+        .unwrap()
+        .is_match("/other")
+    );
+    fs::remove_dir_all(&root).unwrap();
+  }
+
+  #[test]
+  fn single_file_input_processes_only_that_file() {
+    let root = temp_dir("single-file");
+    let input_dir = root.join("mod");
+    write(&input_dir.join("codex/a.codex"), r#"{"title": "A"}"#);
+    write(&input_dir.join("codex/b.codex"), r#"{"title": "B"}"#);
+    let output_dir = root.join("out");
+
+    run(
+      input_dir.join("codex/a.codex"),
+      output_dir.clone(),
+      options_with_config(&root),
+    )
+    .unwrap();
+    assert_eq!(patch_paths(&output_dir.join("a.codex.patch")), ["/title"]);
+    assert!(!output_dir.join("b.codex.patch").exists());
+    assert!(!output_dir.join("codex").exists());
+    fs::remove_dir_all(&root).unwrap();
+  }
+
+  #[test]
+  fn directory_input_processes_whitelisted_files() {
+    let root = temp_dir("directory");
+    let input_dir = root.join("mod");
+    write(&input_dir.join("codex/a.codex"), r#"{"title": "A"}"#);
+    write(
+      &input_dir.join("codex/nested/b.codex"),
+      r#"{"title": "B", "Skipped": "x"}"#,
+    );
+    write(&input_dir.join("notlisted/c.codex"), r#"{"title": "C"}"#);
+    let output_dir = root.join("out");
+
+    run(input_dir, output_dir.clone(), options_with_config(&root)).unwrap();
+    assert_eq!(
+      patch_paths(&output_dir.join("codex/a.codex.patch")),
+      ["/title"]
+    );
+    assert_eq!(
+      patch_paths(&output_dir.join("codex/nested/b.codex.patch")),
+      ["/title"]
+    );
+    assert!(!output_dir.join("notlisted").exists());
+    fs::remove_dir_all(&root).unwrap();
+  }
+
+  #[test]
+  fn zip_input_is_processed_like_a_directory() {
+    let root = temp_dir("zip");
+    let archive_path = root.join("mod.zip");
+    let bytes = archive_bytes(&[
+      TestEntry::stored("codex/a.codex", br#"{"title": "A"}"#),
+      TestEntry::stored("codex/readme.txt", b"not json"),
+      TestEntry::stored("notlisted/c.codex", br#"{"title": "C"}"#),
+      TestEntry::stored("../escape.codex", br#"{"title": "X"}"#),
+    ]);
+    fs::write(&archive_path, bytes).unwrap();
+    let output_dir = root.join("out");
+
+    run(archive_path, output_dir.clone(), options_with_config(&root)).unwrap();
+    assert_eq!(
+      patch_paths(&output_dir.join("codex/a.codex.patch")),
+      ["/title"]
+    );
+    assert!(!output_dir.join("codex/readme.txt.patch").exists());
+    assert!(!output_dir.join("notlisted").exists());
+    // 解压到临时目录时跳过指向目录之外的条目
+    assert!(!std::env::temp_dir().join("escape.codex").exists());
+    fs::remove_dir_all(&root).unwrap();
+  }
+
+  #[test]
+  fn max_ops_per_file_truncates_or_skips() {
+    let root = temp_dir("max-ops");
+    let input_dir = root.join("mod");
+    write(
+      &input_dir.join("codex/a.codex"),
+      r#"{"title": "A", "text": "B"}"#,
+    );
+
+    let output_dir = root.join("truncated");
+    let options = GenOptions {
+      max_ops_per_file: Some(1),
+      ..options_with_config(&root)
+    };
+    run(input_dir.clone(), output_dir.clone(), options).unwrap();
+    assert_eq!(
+      patch_paths(&output_dir.join("codex/a.codex.patch")),
+      ["/title"]
+    );
+
+    let output_dir = root.join("skipped");
+    let options = GenOptions {
+      max_ops_per_file: Some(1),
+      max_ops_action: MaxOpsAction::Skip,
+      ..options_with_config(&root)
+    };
+    run(input_dir, output_dir.clone(), options).unwrap();
+    assert!(!output_dir.join("codex/a.codex.patch").exists());
+    fs::remove_dir_all(&root).unwrap();
+  }
+
+  #[test]
+  fn output_file_names() {
+    let output_dir = Path::new("out");
+    let relative_path = Path::new("items/sword.item");
+    assert_eq!(
+      output_file_path(
+        output_dir,
+        relative_path,
+        false,
+        PatchNaming::Append,
+        OutputFormat::Json
+      ),
+      Path::new("out/items/sword.item.patch")
+    );
+    assert_eq!(
+      output_file_path(
+        output_dir,
+        relative_path,
+        false,
+        PatchNaming::Replace,
+        OutputFormat::Yaml
+      ),
+      Path::new("out/items/sword.patch.yaml")
+    );
+    assert_eq!(
+      output_file_path(
+        output_dir,
+        Path::new("a.config.patch"),
+        true,
+        PatchNaming::Append,
+        OutputFormat::Json
+      ),
+      Path::new("out/a.config.patch")
+    );
+  }
+
+  #[test]
+  fn extension_info() {
+    assert_eq!(
+      get_extension_info(Path::new("a/b.Codex")),
+      ("codex".to_string(), false)
+    );
+    assert_eq!(
+      get_extension_info(Path::new("a/b.config.patch")),
+      ("config.patch".to_string(), true)
+    );
+    assert_eq!(
+      get_extension_info(Path::new("noext")),
+      (String::new(), false)
+    );
+  }
+
+  #[test]
+  fn argument_parsers() {
+    assert_eq!(parse_indent("4"), Ok(Indent::Spaces(4)));
+    assert_eq!(parse_indent("TAB"), Ok(Indent::Tab));
+    assert!(parse_indent("four").is_err());
+    assert_eq!(parse_time_budget("1.5"), Ok(Duration::from_millis(1500)));
+    assert!(parse_time_budget("0").is_err());
+    assert!(parse_time_budget("-1").is_err());
+  }
+
+  #[test]
+  fn overlapping_paths() {
+    let root = temp_dir("overlap");
+    assert!(paths_overlap(&root, &root.join("out")));
+    assert!(paths_overlap(&root.join("a/../out"), &root));
+    assert!(!paths_overlap(&root.join("in"), &root.join("out")));
+    fs::remove_dir_all(&root).unwrap();
+  }
+
+  /// 读取合并输出的单个文件中每个源文件的操作
+  fn single_file_patches(path: &Path) -> serde_json::Map<String, Value> {
+    let merged: Value = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+    merged.as_object().unwrap().clone()
+  }
+
+  #[test]
+  fn single_output_contains_every_per_file_operation() {
+    let root = temp_dir("single-output");
+    let input_dir = root.join("mod");
+    write(
+      &input_dir.join("codex/a.codex"),
+      r#"{"title": "A", "description": "AA"}"#,
+    );
+    write(&input_dir.join("codex/nested/b.codex"), r#"{"title": "B"}"#);
+    let (per_file_dir, single_path) = (root.join("out"), root.join("all.patch"));
+
+    run(
+      input_dir.clone(),
+      per_file_dir.clone(),
+      options_with_config(&root),
+    )
+    .unwrap();
+    let single_options = GenOptions {
+      single_file: Some(single_path.clone()),
+      ..options_with_config(&root)
+    };
+    run(input_dir, root.join("unused"), single_options).unwrap();
+
+    let merged = single_file_patches(&single_path);
+    assert_eq!(
+      merged.keys().collect::<Vec<_>>(),
+      ["codex/a.codex", "codex/nested/b.codex"]
+    );
+    for (source_path, operations) in &merged {
+      let per_file: Value = serde_json::from_str(
+        &fs::read_to_string(per_file_dir.join(format!("{}.patch", source_path))).unwrap(),
+      )
+      .unwrap();
+      assert_eq!(operations, &per_file, "{}", source_path);
+    }
+    fs::remove_dir_all(&root).unwrap();
+  }
+
+  #[test]
+  fn relative_pointers_are_valid_and_unique_across_files() {
+    let root = temp_dir("relative-pointer");
+    let input_dir = root.join("mod");
+    write(&input_dir.join("codex/a.codex"), r#"{"title": "A"}"#);
+    write(&input_dir.join("codex/a~b/a.codex"), r#"{"title": "B"}"#);
+    let single_path = root.join("all.patch");
+    let options = GenOptions {
+      single_file: Some(single_path.clone()),
+      relative_pointer: true,
+      ..options_with_config(&root)
+    };
+    run(input_dir, root.join("unused"), options).unwrap();
+
+    let mut pointers = Vec::new();
+    for (source_path, operations) in single_file_patches(&single_path) {
+      let patch_data = PatchData::from_value(operations).unwrap();
+      json_patch::validate_patch(&patch_data).unwrap();
+      for patch_operation in patch_data.operations() {
+        let pointer = patch_operation["path"].as_str().unwrap().to_string();
+        assert!(
+          pointer.starts_with(&json_patch::path_to_pointer(&source_path)),
+          "{}",
+          pointer
+        );
+        pointers.push(pointer);
       }
     }
+    assert_eq!(
+      pointers,
+      ["/codex/a.codex/title", "/codex/a~0b/a.codex/title"]
+    );
+    fs::remove_dir_all(&root).unwrap();
   }
 
-  (file_extension, is_patch)
+  #[test]
+  fn captured_comments_are_attached_only_with_annotate() {
+    let root = temp_dir("annotate");
+    let input_dir = root.join("mod");
+    write(
+      &input_dir.join("codex/a.codex"),
+      "{\n  // Shown on the book cover\n  \"title\": \"A\"\n}",
+    );
+    let comment_of = |annotate: bool| {
+      let output_dir = root.join(format!("out-{}", annotate));
+      let options = GenOptions {
+        capture_comments: true,
+        annotate,
+        ..options_with_config(&root)
+      };
+      run(input_dir.clone(), output_dir.clone(), options).unwrap();
+      let patch: Value =
+        serde_json::from_str(&fs::read_to_string(output_dir.join("codex/a.codex.patch")).unwrap())
+          .unwrap();
+      patch[0].get("comment").cloned()
+    };
+    assert_eq!(comment_of(false), None);
+    assert_eq!(comment_of(true), Some(json!("Shown on the book cover")));
+    fs::remove_dir_all(&root).unwrap();
+  }
+
+  #[test]
+  fn report_does_not_count_hidden_files() {
+    let root = temp_dir("report-hidden");
+    let input_dir = root.join("mod");
+    write(&input_dir.join("codex/a.codex"), r#"{"title": "A"}"#);
+    write(&input_dir.join("codex/.b.codex"), r#"{"title": "B"}"#);
+    write(&input_dir.join(".git/config"), "");
+    let report_path = root.join("report.json");
+    let options = GenOptions {
+      report: Some(report_path.clone()),
+      ..options_with_config(&root)
+    };
+    run(input_dir, root.join("out"), options).unwrap();
+
+    let report: Value = serde_json::from_str(&fs::read_to_string(&report_path).unwrap()).unwrap();
+    assert_eq!(report["files_scanned"], 1);
+    assert_eq!(report["files_matched"], 1);
+    fs::remove_dir_all(&root).unwrap();
+  }
+
+  #[test]
+  fn single_file_input_checks_overlap() {
+    let root = temp_dir("single-file-overlap");
+    let output_dir = root.join("out");
+    let input_file = output_dir.join("codex/a.codex");
+    write(&input_file, r#"{"title": "A"}"#);
+
+    assert!(
+      run(
+        input_file.clone(),
+        output_dir.clone(),
+        options_with_config(&root)
+      )
+      .is_err()
+    );
+    assert!(!output_dir.join("a.codex.patch").exists());
+    let options = GenOptions {
+      allow_overlap: true,
+      ..options_with_config(&root)
+    };
+    run(input_file, output_dir.clone(), options).unwrap();
+    assert_eq!(patch_paths(&output_dir.join("a.codex.patch")), ["/title"]);
+    fs::remove_dir_all(&root).unwrap();
+  }
+
+  #[test]
+  fn time_budget_stops_early_and_fails() {
+    let root = temp_dir("time-budget");
+    let input_dir = root.join("mod");
+    for index in 0..50 {
+      write(
+        &input_dir.join(format!("codex/{:02}.codex", index)),
+        r#"{"title": "A"}"#,
+      );
+    }
+    let (output_dir, report_path) = (root.join("out"), root.join("report.json"));
+    let options = GenOptions {
+      time_budget: Some(Duration::from_nanos(1)),
+      report: Some(report_path.clone()),
+      ..options_with_config(&root)
+    };
+    assert!(run(input_dir, output_dir.clone(), options).is_err());
+
+    let report: Value = serde_json::from_str(&fs::read_to_string(&report_path).unwrap()).unwrap();
+    let over_budget = report["files_over_budget"].as_u64().unwrap();
+    assert!(over_budget > 0);
+    // 超出时间限制前生成的部分已写入
+    let written = fs::read_dir(output_dir.join("codex"))
+      .map(|entries| entries.count())
+      .unwrap_or(0);
+    assert_eq!(written as u64 + over_budget, 50);
+    fs::remove_dir_all(&root).unwrap();
+  }
 }
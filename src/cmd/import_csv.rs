@@ -0,0 +1,210 @@
+use std::{
+  fs,
+  path::{Component, Path, PathBuf},
+  time::Instant,
+};
+
+use anyhow::{Context, Result, bail};
+use indexmap::IndexMap;
+use serde_json::{Value, json};
+
+use crate::{
+  cmd::generate::{
+    Indent, OutputFormat, PatchNaming, get_extension_info, output_file_path, write_patch_file,
+  },
+  util::{csv, json_patch::PatchData, log::log_info},
+};
+
+/// 运行导入 CSV 的命令：按 source_file 分组，每个源文件生成一个 patch 文件，
+/// 每行生成一个 replace 操作，translation 为空的行会被跳过
+/// 数组元素在导出时已展开为带下标的 JSON Pointer，这里按原样逐个替换，
+/// 这样只翻译了部分元素时也不会覆盖其余元素
+pub fn run(csv_path: PathBuf, output: PathBuf) -> Result<()> {
+  // 计时开始
+  let start_time = Instant::now();
+
+  let csv_str = fs::read_to_string(&csv_path).context(format!(
+    "[ERROR] Failed to read CSV file {}",
+    csv_path.display()
+  ))?;
+  let records = csv::parse(&csv_str).context(format!(
+    "[ERROR] Failed to parse CSV file {}",
+    csv_path.display()
+  ))?;
+
+  // 按表头定位所需的列
+  let Some((header, rows)) = records.split_first() else {
+    bail!("[ERROR] CSV file {} is empty!", csv_path.display());
+  };
+  let column = |name: &str| {
+    header
+      .iter()
+      .position(|column| column == name)
+      .context(format!(
+        "[ERROR] CSV file is missing the \"{}\" column!",
+        name
+      ))
+  };
+  let (source_column, pointer_column, translation_column) = (
+    column("source_file")?,
+    column("json_pointer")?,
+    column("translation")?,
+  );
+
+  // 源文件相对路径 -> replace 操作
+  let mut grouped: IndexMap<&str, Vec<Value>> = IndexMap::new();
+  let mut skipped_count = 0;
+  for (index, row) in rows.iter().enumerate() {
+    // 表头占第 1 行
+    let row_number = index + 2;
+    let cell = |column: usize| row.get(column).map(String::as_str).unwrap_or_default();
+    let (source_file, json_pointer, translation) = (
+      cell(source_column),
+      cell(pointer_column),
+      cell(translation_column),
+    );
+    if translation.is_empty() {
+      skipped_count += 1;
+      continue;
+    }
+    if !is_relative_path(source_file) {
+      bail!(
+        "[ERROR] Row {}: invalid source_file \"{}\", expected a path relative to the Mod folder!",
+        row_number,
+        source_file
+      );
+    }
+    if !json_pointer.is_empty() && !json_pointer.starts_with('/') {
+      bail!(
+        "[ERROR] Row {}: invalid json_pointer \"{}\"!",
+        row_number,
+        json_pointer
+      );
+    }
+    grouped.entry(source_file).or_default().push(json!({
+      "op": "replace",
+      "path": json_pointer,
+      "value": translation
+    }));
+  }
+
+  // 每个源文件写入一个 patch 文件
+  let mut op_count = 0;
+  for (source_file, operations) in &grouped {
+    let relative_path = Path::new(source_file);
+    let (_, is_patch) = get_extension_info(relative_path);
+    let output_file_path = output_file_path(
+      &output,
+      relative_path,
+      is_patch,
+      PatchNaming::Append,
+      OutputFormat::Json,
+    );
+    op_count += operations.len();
+    write_patch_file(
+      &output_file_path,
+      &PatchData::CommonPatch(operations.clone()),
+      OutputFormat::Json,
+      Indent::default(),
+    )
+    .context(format!(
+      "[ERROR] Failed to write patch file to {}",
+      output_file_path.display()
+    ))?;
+  }
+
+  let duration = start_time.elapsed();
+  log_info!(
+    "Imported {} translation(s) into {} file(s), {} row(s) without translation skipped - time elapsed: {}.{:03}s",
+    op_count,
+    grouped.len(),
+    skipped_count,
+    duration.as_secs(),
+    duration.subsec_millis()
+  );
+
+  Ok(())
+}
+
+/// 检查路径是否为不会跳出输出目录的相对路径
+fn is_relative_path(path: &str) -> bool {
+  !path.is_empty()
+    && Path::new(path)
+      .components()
+      .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// 为测试创建空的临时目录
+  fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("neki_lang-test-{}-{}", name, std::process::id()));
+    if dir.exists() {
+      fs::remove_dir_all(&dir).unwrap();
+    }
+    fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  /// 读取生成的 patch 文件
+  fn read_patch(path: &Path) -> Value {
+    serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+  }
+
+  #[test]
+  fn rows_without_translation_are_skipped() {
+    let root = temp_dir("import-csv");
+    let (csv_path, output) = (root.join("in.csv"), root.join("out"));
+    fs::write(
+      &csv_path,
+      "source_file,json_pointer,original,translation\r\n\
+       codex/a.codex,/title,Title,标题\r\n\
+       codex/a.codex,/description,Text,\r\n\
+       codex/b.codex,/title,Other,\r\n\
+       items/c.item.patch,/0/value,\"Say \"\"hi\"\"\",\"说 \"\"你好\"\"\"\r\n",
+    )
+    .unwrap();
+
+    run(csv_path, output.clone()).unwrap();
+
+    assert_eq!(
+      read_patch(&output.join("codex/a.codex.patch")),
+      json!([{"op": "replace", "path": "/title", "value": "标题"}])
+    );
+    // 没有任何译文的源文件不生成 patch
+    assert!(!output.join("codex/b.codex.patch").exists());
+    // patch 文件的 patch 保持原文件名
+    assert_eq!(
+      read_patch(&output.join("items/c.item.patch")),
+      json!([{"op": "replace", "path": "/0/value", "value": "说 \"你好\""}])
+    );
+    fs::remove_dir_all(&root).unwrap();
+  }
+
+  #[test]
+  fn array_elements_are_replaced_one_by_one() {
+    let root = temp_dir("import-csv-array");
+    let (csv_path, output) = (root.join("in.csv"), root.join("out"));
+    fs::write(
+      &csv_path,
+      "source_file,json_pointer,original,translation\n\
+       codex/a.codex,/pages/0,One,一\n\
+       codex/a.codex,/pages/1,Two,\n\
+       codex/a.codex,/pages/2,Three,三\n",
+    )
+    .unwrap();
+
+    run(csv_path, output.clone()).unwrap();
+
+    assert_eq!(
+      read_patch(&output.join("codex/a.codex.patch")),
+      json!([
+        {"op": "replace", "path": "/pages/0", "value": "一"},
+        {"op": "replace", "path": "/pages/2", "value": "三"}
+      ])
+    );
+    fs::remove_dir_all(&root).unwrap();
+  }
+}
@@ -1,8 +1,16 @@
-use std::fs;
+use std::{
+  fs,
+  path::{Path, PathBuf},
+};
 
 use anyhow::{Context, Result, bail};
 
-use crate::cmd::shared::{DEFAULT_DIR_CONFIG, DEFAULT_REGEX_CONFIG};
+use crate::{
+  cmd::shared::{
+    DEFAULT_DIR_CONFIG, DEFAULT_REGEX_CONFIG, MINIMAL_DIR_CONFIG, MINIMAL_REGEX_CONFIG,
+  },
+  util::log::{log_info, log_warn},
+};
 
 enum ConfigStatus {
   AllExist,
@@ -11,84 +19,226 @@ enum ConfigStatus {
   NoneExists,
 }
 
-pub fn run(force: bool) -> Result<()> {
-  println!("[INFO] Initializing configuration files...");
-
-  let exe_dir = std::env::current_exe()
-    .context("[ERROR] Failed to get current executable path!")?
-    .parent()
-    .context("[ERROR] Failed to get parent directory of executable!")?
-    .to_path_buf();
-
-  let dir_path = exe_dir.join("dirs_config.json");
-  let regex_path = exe_dir.join("regex_config.json");
-
-  if !force {
-    let status = match (dir_path.exists(), regex_path.exists()) {
-      (true, true) => ConfigStatus::AllExist,
-      (true, false) => ConfigStatus::DirExists,
-      (false, true) => ConfigStatus::RegexExists,
-      (false, false) => ConfigStatus::NoneExists,
-    };
-
-    match status {
-      ConfigStatus::AllExist => {
-        bail!(
-          "[ERROR] All config files already exist in {}. Use --force to overwrite.",
-          exe_dir.display()
-        )
-      }
-      ConfigStatus::DirExists => {
-        println!(
-          "[WARN] \"dirs_config.json\" already exists in {}. Use --force to overwrite.",
-          exe_dir.display()
-        );
-        println!("[INFO] Writing regex_config.json...");
-
-        fs::write(&regex_path, DEFAULT_REGEX_CONFIG).context(format!(
-          "[ERROR] Failed to write \"regex_config.json\" to {}",
-          regex_path.display()
-        ))?;
-      }
-      ConfigStatus::RegexExists => {
-        println!(
-          "[WARN] \"regex_config.json\" already exists in {}. Use --force to overwrite.",
-          exe_dir.display()
-        );
-        println!("[INFO] Writing \"dirs_config.json\"...");
-
-        fs::write(&dir_path, DEFAULT_DIR_CONFIG).context(format!(
-          "[ERROR] Failed to write \"dirs_config.json\" to {}",
-          dir_path.display()
-        ))?;
-      }
-      ConfigStatus::NoneExists => {
-        println!("[INFO] Writing \"dirs_config.json\" and \"regex_config.json\"...");
-        fs::write(&dir_path, DEFAULT_DIR_CONFIG).context(format!(
-          "[ERROR] Failed to write \"dirs_config.json\" to {}",
-          dir_path.display()
-        ))?;
-        fs::write(&regex_path, DEFAULT_REGEX_CONFIG).context(format!(
-          "[ERROR] Failed to write \"regex_config.json\" to {}",
-          regex_path.display()
-        ))?;
-      }
+pub fn run(force: bool, minimal: bool, list: bool, output_dir: Option<PathBuf>) -> Result<()> {
+  // 精简模板仅包含一个示例目录和一个示例正则
+  let (dir_config, regex_config) = if minimal {
+    (MINIMAL_DIR_CONFIG, MINIMAL_REGEX_CONFIG)
+  } else {
+    (DEFAULT_DIR_CONFIG, DEFAULT_REGEX_CONFIG)
+  };
+
+  // 只输出配置内容，不写入任何文件
+  if list {
+    println!("==> dirs_config.json <==");
+    println!("{}", dir_config.trim_end());
+    println!();
+    println!("==> regex_config.json <==");
+    println!("{}", regex_config.trim_end());
+    return Ok(());
+  }
+
+  log_info!("Initializing configuration files...");
+
+  // 未指定输出目录时，写入到可执行文件目录
+  let config_dir = match output_dir {
+    Some(dir) => {
+      fs::create_dir_all(&dir).context(format!(
+        "[ERROR] Failed to create output directory {}",
+        dir.display()
+      ))?;
+      dir
+    }
+    None => std::env::current_exe()
+      .context("[ERROR] Failed to get current executable path!")?
+      .parent()
+      .context("[ERROR] Failed to get parent directory of executable!")?
+      .to_path_buf(),
+  };
+
+  let dir_path = config_dir.join("dirs_config.json");
+  let regex_path = config_dir.join("regex_config.json");
+
+  // 强制覆盖时视为配置文件都不存在
+  let status = match (force, dir_path.exists(), regex_path.exists()) {
+    (true, _, _) => ConfigStatus::NoneExists,
+    (false, true, true) => ConfigStatus::AllExist,
+    (false, true, false) => ConfigStatus::DirExists,
+    (false, false, true) => ConfigStatus::RegexExists,
+    (false, false, false) => ConfigStatus::NoneExists,
+  };
+
+  match status {
+    ConfigStatus::AllExist => {
+      bail!(
+        "[ERROR] All config files already exist in {}. Use --force to overwrite.",
+        config_dir.display()
+      )
+    }
+    ConfigStatus::DirExists => {
+      log_warn!(
+        "\"dirs_config.json\" already exists in {}. Use --force to overwrite.",
+        config_dir.display()
+      );
+      log_info!("Writing regex_config.json...");
+
+      fs::write(&regex_path, regex_config).context(format!(
+        "[ERROR] Failed to write \"regex_config.json\" to {}",
+        regex_path.display()
+      ))?;
+    }
+    ConfigStatus::RegexExists => {
+      log_warn!(
+        "\"regex_config.json\" already exists in {}. Use --force to overwrite.",
+        config_dir.display()
+      );
+      log_info!("Writing \"dirs_config.json\"...");
+
+      fs::write(&dir_path, dir_config).context(format!(
+        "[ERROR] Failed to write \"dirs_config.json\" to {}",
+        dir_path.display()
+      ))?;
+    }
+    ConfigStatus::NoneExists => {
+      // 强制覆盖前先备份已有的配置文件，备份失败时不覆盖
+      backup_if_exists(&dir_path)?;
+      backup_if_exists(&regex_path)?;
+      log_info!("Writing \"dirs_config.json\" and \"regex_config.json\"...");
+      fs::write(&dir_path, dir_config).context(format!(
+        "[ERROR] Failed to write \"dirs_config.json\" to {}",
+        dir_path.display()
+      ))?;
+      fs::write(&regex_path, regex_config).context(format!(
+        "[ERROR] Failed to write \"regex_config.json\" to {}",
+        regex_path.display()
+      ))?;
     }
   }
-  println!("[INFO] Writing \"dirs_config.json\" and \"regex_config.json\"...");
-  fs::write(&dir_path, DEFAULT_DIR_CONFIG).context(format!(
-    "[ERROR] Failed to write \"dirs_config.json\" to {}",
-    dir_path.display()
-  ))?;
-  fs::write(&regex_path, DEFAULT_REGEX_CONFIG).context(format!(
-    "[ERROR] Failed to write \"regex_config.json\" to {}",
-    regex_path.display()
-  ))?;
 
-  println!(
-    "[INFO] Configuration files initialized in {}",
-    exe_dir.display()
+  log_info!(
+    "Configuration files initialized in {}",
+    config_dir.display()
   );
 
   Ok(())
 }
+
+/// 文件已存在时复制为 "<文件名>.bak"，该备份也已存在时依次尝试 ".bak.1"、".bak.2"……
+fn backup_if_exists(path: &Path) -> Result<()> {
+  if !path.exists() {
+    return Ok(());
+  }
+  let file_name = path
+    .file_name()
+    .context("[ERROR] Failed to get config file name!")?
+    .to_string_lossy();
+  let mut backup_path = path.with_file_name(format!("{}.bak", file_name));
+  let mut index = 1;
+  while backup_path.exists() {
+    backup_path = path.with_file_name(format!("{}.bak.{}", file_name, index));
+    index += 1;
+  }
+  fs::copy(path, &backup_path).context(format!(
+    "[ERROR] Failed to back up {} to {}, nothing was overwritten",
+    path.display(),
+    backup_path.display()
+  ))?;
+  log_info!("Backed up {} to {}", path.display(), backup_path.display());
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::util::{
+    json5,
+    patterns::{PatternConfig, RawPatternConfig},
+  };
+  use std::collections::HashSet;
+
+  /// 为测试创建空的临时目录
+  fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("neki_lang-test-{}-{}", name, std::process::id()));
+    if dir.exists() {
+      fs::remove_dir_all(&dir).unwrap();
+    }
+    fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  /// 解析一组配置模板，确认其能被生成命令加载
+  fn compile(dir_config: &str, regex_config: &str) -> (HashSet<String>, PatternConfig) {
+    let dirs = serde_json::from_value(json5::parse(dir_config).unwrap()).unwrap();
+    let raw_config: RawPatternConfig =
+      serde_json::from_value(json5::parse(regex_config).unwrap()).unwrap();
+    (dirs, PatternConfig::from_raw_config(raw_config).unwrap())
+  }
+
+  #[test]
+  fn minimal_templates_are_smaller_and_compile() {
+    assert!(MINIMAL_DIR_CONFIG.len() < DEFAULT_DIR_CONFIG.len());
+    assert!(MINIMAL_REGEX_CONFIG.len() < DEFAULT_REGEX_CONFIG.len());
+    let (dirs, patterns) = compile(MINIMAL_DIR_CONFIG, MINIMAL_REGEX_CONFIG);
+    assert!(!dirs.is_empty());
+    assert!(patterns.contains_extension("codex"));
+    compile(DEFAULT_DIR_CONFIG, DEFAULT_REGEX_CONFIG);
+  }
+
+  #[test]
+  fn output_dir_receives_the_configs() {
+    let root = temp_dir("init-output-dir");
+    let config_dir = root.join("nested/config");
+    run(false, true, false, Some(config_dir.clone())).unwrap();
+    assert_eq!(
+      fs::read_to_string(config_dir.join("dirs_config.json")).unwrap(),
+      MINIMAL_DIR_CONFIG
+    );
+    assert_eq!(
+      fs::read_to_string(config_dir.join("regex_config.json")).unwrap(),
+      MINIMAL_REGEX_CONFIG
+    );
+    // 已有配置且未使用 --force 时报错
+    assert!(run(false, true, false, Some(config_dir)).is_err());
+    fs::remove_dir_all(&root).unwrap();
+  }
+
+  #[test]
+  fn list_writes_no_files() {
+    let root = temp_dir("init-list");
+    let config_dir = root.join("config");
+    run(false, false, true, Some(config_dir.clone())).unwrap();
+    assert!(!config_dir.exists());
+    assert_eq!(fs::read_dir(&root).unwrap().count(), 0);
+    fs::remove_dir_all(&root).unwrap();
+  }
+
+  #[test]
+  fn force_backs_up_existing_configs() {
+    let root = temp_dir("init-backup");
+    // 新写入的配置没有可备份的旧文件
+    run(true, true, false, Some(root.clone())).unwrap();
+    assert!(!root.join("dirs_config.json.bak").exists());
+    assert!(!root.join("regex_config.json.bak").exists());
+
+    fs::write(root.join("regex_config.json"), "{\"edited\": []}").unwrap();
+    run(true, true, false, Some(root.clone())).unwrap();
+    assert_eq!(
+      fs::read_to_string(root.join("regex_config.json.bak")).unwrap(),
+      "{\"edited\": []}"
+    );
+    assert_eq!(
+      fs::read_to_string(root.join("dirs_config.json.bak")).unwrap(),
+      MINIMAL_DIR_CONFIG
+    );
+    // 已有的备份不会被覆盖
+    run(true, false, false, Some(root.clone())).unwrap();
+    assert_eq!(
+      fs::read_to_string(root.join("regex_config.json.bak")).unwrap(),
+      "{\"edited\": []}"
+    );
+    assert_eq!(
+      fs::read_to_string(root.join("regex_config.json.bak.1")).unwrap(),
+      MINIMAL_REGEX_CONFIG
+    );
+    fs::remove_dir_all(&root).unwrap();
+  }
+}
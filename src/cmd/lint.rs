@@ -0,0 +1,74 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::{Context, Result, bail};
+
+use crate::{
+  cmd::generate::{WalkOptions, collect_input_files, get_extension_info, load_config},
+  util::{json_patch, json5, log::log_info, parallel},
+};
+
+/// 运行检查前缀的命令：列出输入中已带有翻译标记的待翻译字符串，不生成任何 patch
+/// 这些字符串在生成时会被跳过，迁移已部分翻译的 Mod 时可据此确认哪些无需再提交
+pub fn run(input: PathBuf, config_dir: Option<PathBuf>) -> Result<()> {
+  // 加载配置文件，使用与生成命令相同的白名单和后缀名过滤
+  let (dir_whitelist, regex_config) = load_config(config_dir.as_deref(), Some(&input))?;
+  let input_files = collect_input_files(
+    &input,
+    &dir_whitelist,
+    &regex_config,
+    &WalkOptions::default(),
+  )?;
+
+  // 并行读取、解析文件并查找已标记的字符串
+  let results = parallel::par_map(input_files, |file_path| {
+    let result = fs::read_to_string(&file_path)
+      .context("Failed to read file")
+      .and_then(|json_str| json5::parse(&json_str).context("Failed to parse file"))
+      .map(|json_value| {
+        let (ext, is_patch) = get_extension_info(&file_path);
+        let relative_path = file_path.strip_prefix(&input).unwrap_or(&file_path);
+        json_patch::find_marked_strings(is_patch, &json_value, &ext, relative_path, &regex_config)
+      });
+    (file_path, result)
+  });
+
+  let mut marked_count = 0;
+  let mut marked_file_count = 0;
+  let mut failed_files = Vec::new();
+  for (file_path, result) in results {
+    let strings = match result {
+      Ok(strings) => strings,
+      Err(e) => {
+        failed_files.push((file_path, e));
+        continue;
+      }
+    };
+    if strings.is_empty() {
+      continue;
+    }
+    // 按文件分组输出
+    println!("{}", file_path.strip_prefix(&input)?.display());
+    for (pointer, value) in &strings {
+      println!("  {}: {}", pointer, serde_json::to_string(value)?);
+    }
+    marked_count += strings.len();
+    marked_file_count += 1;
+  }
+
+  log_info!(
+    "{} already marked string(s) in {} file(s)",
+    marked_count,
+    marked_file_count
+  );
+
+  // 汇总处理失败的文件
+  if !failed_files.is_empty() {
+    println!("[ERROR] {} file(s) failed:", failed_files.len());
+    for (file_path, e) in &failed_files {
+      println!("  {}: {:#}", file_path.display(), e);
+    }
+    bail!("[ERROR] {} file(s) failed to process!", failed_files.len());
+  }
+
+  Ok(())
+}
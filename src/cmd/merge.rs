@@ -0,0 +1,316 @@
+use std::{
+  collections::HashMap,
+  fs,
+  path::{Path, PathBuf},
+  time::Instant,
+};
+
+use anyhow::{Context, Result, bail};
+use indexmap::IndexMap;
+use serde_json::Value;
+use walkdir::WalkDir;
+
+use crate::{
+  cmd::generate::{Indent, OutputFormat, write_patch_file},
+  util::{
+    json_patch::PatchData,
+    json5,
+    log::{log_info, log_warn},
+    parallel,
+  },
+};
+
+/// 合并时发现的冲突：同一文件的同一路径在不同输入目录中被替换为不同的值
+struct Conflict {
+  relative_path: PathBuf,
+  json_pointer: String,
+  /// 保留的值所在的输入目录
+  kept_from: PathBuf,
+  /// 被丢弃的值所在的输入目录
+  dropped_from: PathBuf,
+}
+
+/// 运行合并命令：把多个生成的模板目录合并到输出目录
+/// 多个输入目录中都存在的 patch 文件按输入顺序拼接操作，
+/// 同一路径的值互相冲突时保留先出现的操作并报告冲突
+pub fn run(inputs: Vec<PathBuf>, output: PathBuf) -> Result<()> {
+  // 计时开始
+  let start_time = Instant::now();
+
+  // 按输入顺序收集所有 patch 文件
+  let mut patch_files = Vec::new();
+  for (input_index, input_dir) in inputs.iter().enumerate() {
+    if !input_dir.is_dir() {
+      bail!(
+        "[ERROR] Input directory {} does not exist!",
+        input_dir.display()
+      );
+    }
+    patch_files.extend(
+      WalkDir::new(input_dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| {
+          e.file_type().is_file() && e.path().extension().is_some_and(|ext| ext == "patch")
+        })
+        .map(|e| (input_index, e.into_path())),
+    );
+  }
+
+  // 并行读取并解析
+  let results = parallel::par_map(patch_files, |(input_index, file_path)| {
+    let result = fs::read_to_string(&file_path)
+      .context("Failed to read file")
+      .and_then(|json_str| json5::parse(&json_str).context("Failed to parse file"))
+      .and_then(|patch_value| {
+        PatchData::from_value(patch_value)
+          .context("Not a patch file: expected an array of operations or batches")
+      });
+    (input_index, file_path, result)
+  });
+
+  // 相对路径 -> 各输入目录中的 patch（按输入顺序）
+  let mut grouped: IndexMap<PathBuf, Vec<(usize, PatchData)>> = IndexMap::new();
+  let mut failed_files = Vec::new();
+  for (input_index, file_path, result) in results {
+    match result {
+      Ok(patch_data) => {
+        let relative_path = file_path.strip_prefix(&inputs[input_index])?.to_path_buf();
+        grouped
+          .entry(relative_path)
+          .or_default()
+          .push((input_index, patch_data));
+      }
+      Err(e) => failed_files.push((file_path, e)),
+    }
+  }
+  grouped.sort_keys();
+
+  // 合并并写入
+  let mut conflicts = Vec::new();
+  let mut shared_file_count = 0;
+  for (relative_path, patches) in grouped {
+    if patches.len() > 1 {
+      shared_file_count += 1;
+    }
+    let merged = merge_patches(&relative_path, patches, &inputs, &mut conflicts);
+    write_patch_file(
+      &output.join(&relative_path),
+      &merged,
+      OutputFormat::Json,
+      Indent::default(),
+    )
+    .context(format!(
+      "[ERROR] Failed to write patch file to {}",
+      output.join(&relative_path).display()
+    ))?;
+  }
+
+  let duration = start_time.elapsed();
+  log_info!(
+    "Merged {} input(s), {} file(s) present in more than one input - time elapsed: {}.{:03}s",
+    inputs.len(),
+    shared_file_count,
+    duration.as_secs(),
+    duration.subsec_millis()
+  );
+
+  // 报告冲突
+  if !conflicts.is_empty() {
+    log_warn!(
+      "{} conflicting operation(s), the earlier input was kept:",
+      conflicts.len()
+    );
+    for conflict in &conflicts {
+      log_warn!(
+        "  {}:{} kept from {}, dropped from {}",
+        conflict.relative_path.display(),
+        conflict.json_pointer,
+        conflict.kept_from.display(),
+        conflict.dropped_from.display()
+      );
+    }
+  }
+
+  // 汇总处理失败的文件
+  if !failed_files.is_empty() {
+    println!("[ERROR] {} file(s) failed:", failed_files.len());
+    for (file_path, e) in &failed_files {
+      println!("  {}: {:#}", file_path.display(), e);
+    }
+    bail!("[ERROR] {} file(s) failed to process!", failed_files.len());
+  }
+
+  Ok(())
+}
+
+/// 按输入顺序拼接同一文件的多个 patch，去掉与先前输入冲突的条目和完全相同的条目
+fn merge_patches(
+  relative_path: &Path,
+  patches: Vec<(usize, PatchData)>,
+  inputs: &[PathBuf],
+  conflicts: &mut Vec<Conflict>,
+) -> PatchData {
+  // JSON Pointer -> (值, 所在输入目录序号)
+  let mut seen: HashMap<String, (Value, usize)> = HashMap::new();
+  let mut merged: Option<PatchData> = None;
+
+  for (input_index, mut patch_data) in patches {
+    // 去掉与先前输入冲突的条目（BatchesPatch中整批去掉）
+    patch_data.retain(|patch_operation| {
+      let Some((json_pointer, value)) = assigned_value(patch_operation) else {
+        return true;
+      };
+      match seen.get(json_pointer) {
+        Some((kept_value, kept_index)) if *kept_index != input_index && kept_value != value => {
+          conflicts.push(Conflict {
+            relative_path: relative_path.to_path_buf(),
+            json_pointer: json_pointer.to_string(),
+            kept_from: inputs[*kept_index].clone(),
+            dropped_from: inputs[input_index].clone(),
+          });
+          false
+        }
+        _ => true,
+      }
+    });
+    // 记录保留下来的值
+    for patch_operation in patch_data.operations() {
+      if let Some((json_pointer, value)) = assigned_value(patch_operation) {
+        seen
+          .entry(json_pointer.to_string())
+          .or_insert_with(|| (value.clone(), input_index));
+      }
+    }
+    match &mut merged {
+      Some(merged) => merged.extend(patch_data),
+      None => merged = Some(patch_data),
+    }
+  }
+
+  let mut merged = merged.unwrap_or(PatchData::CommonPatch(Vec::new()));
+  merged.dedup();
+  merged
+}
+
+/// 获取 replace/add 操作的路径和写入的值
+/// 以 `/-` 结尾的 add 是向数组末尾追加，每次都写入新的位置，不参与冲突检测
+fn assigned_value(patch_operation: &Value) -> Option<(&str, &Value)> {
+  match patch_operation["op"].as_str() {
+    Some("replace") | Some("add") => {
+      let json_pointer = patch_operation["path"].as_str()?;
+      if json_pointer.ends_with("/-") {
+        return None;
+      }
+      Some((json_pointer, patch_operation.get("value")?))
+    }
+    _ => None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  /// 为测试创建空的临时目录
+  fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("neki_lang-test-{}-{}", name, std::process::id()));
+    if dir.exists() {
+      fs::remove_dir_all(&dir).unwrap();
+    }
+    fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  /// 写入文件，自动创建上级目录
+  fn write(path: &Path, contents: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+  }
+
+  #[test]
+  fn shared_files_are_concatenated_and_conflicts_keep_the_earlier_input() {
+    let root = temp_dir("merge-trees");
+    let (first, second, output) = (root.join("a"), root.join("b"), root.join("out"));
+    write(
+      &first.join("items/sword.item.patch"),
+      r#"[{"op": "replace", "path": "/shortdescription", "value": "剑"},
+          {"op": "replace", "path": "/description", "value": "一把剑"}]"#,
+    );
+    write(
+      &second.join("items/sword.item.patch"),
+      r#"[{"op": "replace", "path": "/shortdescription", "value": "长剑"},
+          {"op": "replace", "path": "/category", "value": "武器"}]"#,
+    );
+    write(
+      &second.join("items/shield.item.patch"),
+      r#"[{"op": "replace", "path": "/shortdescription", "value": "盾"}]"#,
+    );
+
+    run(vec![first, second], output.clone()).unwrap();
+
+    let merged: Value =
+      serde_json::from_str(&fs::read_to_string(output.join("items/sword.item.patch")).unwrap())
+        .unwrap();
+    assert_eq!(
+      merged,
+      json!([
+        {"op": "replace", "path": "/shortdescription", "value": "剑"},
+        {"op": "replace", "path": "/description", "value": "一把剑"},
+        {"op": "replace", "path": "/category", "value": "武器"}
+      ])
+    );
+    // 只存在于一个输入中的文件原样输出
+    assert!(output.join("items/shield.item.patch").is_file());
+  }
+
+  #[test]
+  fn batches_patches_drop_the_whole_conflicting_batch() {
+    let patches = vec![
+      (
+        0,
+        PatchData::from_value(json!([[{"op": "replace", "path": "/name", "value": "甲"}]]))
+          .unwrap(),
+      ),
+      (
+        1,
+        PatchData::from_value(json!([
+          [{"op": "replace", "path": "/name", "value": "乙"}],
+          [{"op": "replace", "path": "/title", "value": "丙"}]
+        ]))
+        .unwrap(),
+      ),
+    ];
+    let inputs = [PathBuf::from("a"), PathBuf::from("b")];
+    let mut conflicts = Vec::new();
+    let merged = merge_patches(Path::new("x.patch"), patches, &inputs, &mut conflicts);
+
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].json_pointer, "/name");
+    assert_eq!(conflicts[0].dropped_from, PathBuf::from("b"));
+    assert_eq!(merged.operations().len(), 2);
+  }
+
+  #[test]
+  fn array_appends_are_not_conflicts() {
+    let patches = vec![
+      (
+        0,
+        PatchData::from_value(json!([{"op": "add", "path": "/tags/-", "value": "甲"}])).unwrap(),
+      ),
+      (
+        1,
+        PatchData::from_value(json!([{"op": "add", "path": "/tags/-", "value": "乙"}])).unwrap(),
+      ),
+    ];
+    let inputs = [PathBuf::from("a"), PathBuf::from("b")];
+    let mut conflicts = Vec::new();
+    let merged = merge_patches(Path::new("x.patch"), patches, &inputs, &mut conflicts);
+
+    assert!(conflicts.is_empty());
+    let values: Vec<&Value> = merged.operations().iter().map(|x| &x["value"]).collect();
+    assert_eq!(values, [&json!("甲"), &json!("乙")]);
+  }
+}
@@ -6,3 +6,11 @@ pub const DEFAULT_REGEX_CONFIG: &str = include_str!(concat!(
   env!("CARGO_MANIFEST_DIR"),
   "/res/json/regex_config.json"
 ));
+pub const MINIMAL_DIR_CONFIG: &str = include_str!(concat!(
+  env!("CARGO_MANIFEST_DIR"),
+  "/res/json/dirs_config_minimal.json"
+));
+pub const MINIMAL_REGEX_CONFIG: &str = include_str!(concat!(
+  env!("CARGO_MANIFEST_DIR"),
+  "/res/json/regex_config_minimal.json"
+));
@@ -0,0 +1,154 @@
+use std::{
+  collections::{BTreeMap, HashSet},
+  fs,
+  path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, bail};
+
+use crate::{
+  cmd::generate::{WalkOptions, collect_input_files, get_extension_info, load_config},
+  util::{
+    json_patch::{self, PatchOptions},
+    json5, parallel,
+  },
+};
+
+/// 一组字符串的统计：字符串数和总字符数
+#[derive(Default)]
+struct Tally {
+  strings: usize,
+  chars: usize,
+}
+
+impl Tally {
+  fn add(&mut self, string_value: &str) {
+    self.strings += 1;
+    self.chars += string_value.chars().count();
+  }
+}
+
+/// 运行统计命令：统计 Mod 中待翻译字符串的数量和长度，不写入任何文件
+pub fn run(input: PathBuf, config_dir: Option<PathBuf>) -> Result<()> {
+  // 加载配置文件，使用与生成命令相同的白名单和后缀名过滤
+  let (dir_whitelist, regex_config) = load_config(config_dir.as_deref(), Some(&input))?;
+  let input_files = collect_input_files(
+    &input,
+    &dir_whitelist,
+    &regex_config,
+    &WalkOptions::default(),
+  )?;
+  let file_count = input_files.len();
+
+  // 并行读取、解析文件并提取字符串
+  let results = parallel::par_map(input_files, |file_path| {
+    let result = fs::read_to_string(&file_path)
+      .context("Failed to read file")
+      .and_then(|json_str| json5::parse(&json_str).context("Failed to parse file"))
+      .map(|json_value| {
+        let (ext, is_patch) = get_extension_info(&file_path);
+        let relative_path = file_path.strip_prefix(&input).unwrap_or(&file_path);
+        let patch_data = json_patch::generate_patch(
+          is_patch,
+          &json_value,
+          &ext,
+          relative_path,
+          &regex_config,
+          None,
+          &PatchOptions::default(),
+        );
+        let strings = json_patch::marked_strings(
+          &patch_data,
+          regex_config.resolve_marker(&ext, relative_path),
+        );
+        (ext, strings)
+      });
+    (file_path, result)
+  });
+
+  let mut total = Tally::default();
+  let mut unique_strings = HashSet::new();
+  let mut extension_tallies: BTreeMap<String, Tally> = BTreeMap::new();
+  let mut directory_tallies: BTreeMap<String, Tally> = BTreeMap::new();
+  let mut failed_files = Vec::new();
+  for (file_path, result) in results {
+    let (ext, strings) = match result {
+      Ok(file_strings) => file_strings,
+      Err(e) => {
+        failed_files.push((file_path, e));
+        continue;
+      }
+    };
+    let directory = top_level_directory(file_path.strip_prefix(&input)?);
+    for (_, string_value) in strings {
+      total.add(&string_value);
+      extension_tallies
+        .entry(ext.clone())
+        .or_default()
+        .add(&string_value);
+      directory_tallies
+        .entry(directory.clone())
+        .or_default()
+        .add(&string_value);
+      unique_strings.insert(string_value);
+    }
+  }
+
+  // 输出统计结果
+  println!("Files scanned:      {}", file_count);
+  println!("Strings matched:    {}", total.strings);
+  println!("Unique strings:     {}", unique_strings.len());
+  println!("Total length:       {}", total.chars);
+  println!(
+    "Average length:     {:.1}",
+    if total.strings == 0 {
+      0.0
+    } else {
+      total.chars as f64 / total.strings as f64
+    }
+  );
+  println!();
+  print_table("Extension", &extension_tallies);
+  println!();
+  print_table("Directory", &directory_tallies);
+
+  // 汇总处理失败的文件
+  if !failed_files.is_empty() {
+    println!("[ERROR] {} file(s) failed:", failed_files.len());
+    for (file_path, e) in &failed_files {
+      println!("  {}: {:#}", file_path.display(), e);
+    }
+    bail!("[ERROR] {} file(s) failed to process!", failed_files.len());
+  }
+
+  Ok(())
+}
+
+/// 获取相对路径的第一级目录，位于输入目录根部的文件记为 "."
+fn top_level_directory(relative_path: &Path) -> String {
+  let mut components = relative_path.components();
+  match (components.next(), components.next()) {
+    (Some(first), Some(_)) => first.as_os_str().to_string_lossy().to_string(),
+    _ => ".".to_string(),
+  }
+}
+
+/// 输出按名称分组的统计表
+fn print_table(title: &str, tallies: &BTreeMap<String, Tally>) {
+  let width = tallies
+    .keys()
+    .map(|name| name.len())
+    .max()
+    .unwrap_or(0)
+    .max(title.len());
+  println!(
+    "  {:<width$}  {:>8}  {:>10}",
+    title, "Strings", "Characters"
+  );
+  for (name, tally) in tallies {
+    println!(
+      "  {:<width$}  {:>8}  {:>10}",
+      name, tally.strings, tally.chars
+    );
+  }
+}
@@ -0,0 +1,68 @@
+use std::{
+  fs,
+  path::{Path, PathBuf},
+  time::Instant,
+};
+
+use anyhow::{Result, bail};
+
+use crate::{
+  cmd::generate::{WalkOptions, collect_input_files, load_config},
+  util::{json5, log::log_info, parallel},
+};
+
+/// 运行检查命令：只解析输入目录中的文件，不生成任何输出
+pub fn run(input: PathBuf, config_dir: Option<PathBuf>) -> Result<()> {
+  // 计时开始
+  let start_time = Instant::now();
+
+  // 加载配置文件，使用与生成命令相同的白名单和后缀名过滤
+  let (dir_whitelist, regex_config) = load_config(config_dir.as_deref(), Some(&input))?;
+  let input_files = collect_input_files(
+    &input,
+    &dir_whitelist,
+    &regex_config,
+    &WalkOptions::default(),
+  )?;
+  let file_count = input_files.len();
+
+  // 并行读取并解析文件，只保留出错的文件
+  let failed_files: Vec<(PathBuf, String)> = parallel::par_map(input_files, |file_path| {
+    let error = validate_file(&file_path).err();
+    (file_path, error)
+  })
+  .into_iter()
+  .filter_map(|(file_path, error)| error.map(|e| (file_path, e)))
+  .collect();
+
+  let duration = start_time.elapsed();
+  log_info!(
+    "Validation completed - time elapsed: {}.{:03}s",
+    duration.as_secs(),
+    duration.subsec_millis()
+  );
+
+  if !failed_files.is_empty() {
+    println!("[ERROR] {} file(s) failed:", failed_files.len());
+    for (file_path, e) in &failed_files {
+      println!("  {}: {}", file_path.display(), e);
+    }
+    bail!(
+      "[ERROR] {} of {} file(s) failed to validate!",
+      failed_files.len(),
+      file_count
+    );
+  }
+
+  log_info!("{} file(s) validated", file_count);
+
+  Ok(())
+}
+
+/// 读取并解析单个文件，返回错误信息
+fn validate_file(file_path: &Path) -> Result<(), String> {
+  let json_str =
+    fs::read_to_string(file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+  json5::parse(&json_str).map_err(|e| e.message)?;
+  Ok(())
+}
@@ -1,10 +1,30 @@
 pub mod util {
+  pub mod csv;
+  pub mod glob;
   pub mod json5;
   pub mod json_patch;
+  pub mod log;
+  pub mod parallel;
   pub mod patterns;
+  pub mod random;
+  pub mod text_diff;
+  pub mod timestamp;
+  pub mod yaml;
+  pub mod zip;
 }
 pub mod cmd {
+  pub mod apply;
+  pub mod check_config;
+  pub mod completions;
+  pub mod diff;
+  pub mod export_csv;
+  pub mod extract;
   pub mod generate;
+  pub mod import_csv;
   pub mod init;
+  pub mod lint;
+  pub mod merge;
   mod shared;
+  pub mod stats;
+  pub mod validate;
 }
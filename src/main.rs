@@ -1,47 +1,476 @@
-use std::path::PathBuf;
+use std::{
+  path::PathBuf,
+  time::{Duration, SystemTime},
+};
 
 use anyhow::Result;
-use clap::{Parser, Subcommand};
-use neki_lang::cmd;
+use clap::{ArgAction, CommandFactory, Parser, Subcommand};
+use neki_lang::{
+  cmd::{
+    self,
+    completions::Shell,
+    generate::{GenOptions, Indent, MaxOpsAction, OutputFormat, PatchNaming},
+  },
+  util::{
+    json_patch::{NewlineMode, OpKind},
+    log,
+    patterns::NumericKeyPolicy,
+  },
+};
 
 #[derive(Parser)]
 #[clap(author, version, about, long_about = None)]
 struct Cli {
   #[command(subcommand)]
   command: Commands,
+
+  /// Print more details (-vv also prints every processed file)
+  #[arg(short, long, action = ArgAction::Count, global = true)]
+  verbose: u8,
+
+  /// Only print errors
+  #[arg(short, long, global = true, conflicts_with = "verbose")]
+  quiet: bool,
 }
 
+// 命令行参数只解析一次，Gen 的参数较多也无需装箱
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand)]
 enum Commands {
   /// Generate language template
   Gen {
-    /// Input directory (Mod folder)
-    #[arg(short, long)]
-    input: PathBuf,
+    /// Input directory (Mod folder), a .zip archive of it, or a single file to process on its own
+    #[arg(short, long, required_unless_present = "stdin")]
+    input: Option<PathBuf>,
     /// Output directory
-    #[arg(short, long)]
-    output: PathBuf,
+    #[arg(short, long, required_unless_present = "stdin")]
+    output: Option<PathBuf>,
+    /// Read a single file from stdin and write its patch to stdout
+    #[arg(
+      long,
+      requires = "ext",
+      conflicts_with_all = [
+        "input", "output", "base_dir", "with_reverse", "single", "report", "since", "since_last",
+        "dry_run", "show_diff",
+      ]
+    )]
+    stdin: bool,
+    /// File extension of the stdin input (e.g. "codex" or "config.patch")
+    #[arg(long, requires = "stdin")]
+    ext: Option<String>,
     /// To generate test operation for every replace patch operation
     #[arg(short, long)]
     test: bool,
+    /// Include the original string in each test operation so it fails if the source changed
+    #[arg(long, requires = "test")]
+    test_value: bool,
+    /// Maximum number of patch operations per output file
+    #[arg(long, value_name = "N")]
+    max_ops_per_file: Option<usize>,
+    /// What to do with a file exceeding --max-ops-per-file
+    #[arg(long, value_enum, default_value_t = MaxOpsAction::Truncate)]
+    max_ops_action: MaxOpsAction,
+    /// Capture comments preceding each translatable field (listed with -v)
+    #[arg(long)]
+    capture_comments: bool,
+    /// Attach captured comments to their patch operations as a "comment" field
+    #[arg(long, requires = "capture_comments")]
+    annotate: bool,
+    /// Report strings duplicated across files in "string_table.json"
+    #[arg(long)]
+    dedupe_across_files: bool,
+    /// Apply each generated patch to a copy of its source and report unresolved paths
+    #[arg(long)]
+    roundtrip_check: bool,
+    /// How to handle paths going through numeric object keys (e.g. "0"),
+    /// overriding "numeric_keys" in the regex config [default: warn]
+    #[arg(long, value_enum)]
+    numeric_keys: Option<NumericKeyPolicy>,
+    /// Also write reverse patches restoring the original strings to this directory
+    #[arg(long, value_name = "DIR")]
+    with_reverse: Option<PathBuf>,
+    /// Print a random sample of N translatable strings with their file and pointer
+    #[arg(long, value_name = "N")]
+    sample: Option<usize>,
+    /// Seed for --sample, for a reproducible sample
+    #[arg(long, value_name = "SEED", requires = "sample")]
+    sample_seed: Option<u64>,
+    /// Also generate operations for empty and whitespace-only strings
+    #[arg(long)]
+    include_blank: bool,
+    /// Abort on the first file that fails to read or parse
+    #[arg(long)]
+    strict: bool,
+    /// Exit successfully even if some files failed
+    #[arg(long, conflicts_with = "strict")]
+    ignore_errors: bool,
+    /// Directory containing the config files (defaults to the executable's directory)
+    #[arg(long)]
+    config_dir: Option<PathBuf>,
+    /// Report what would be written without touching disk
+    #[arg(long)]
+    dry_run: bool,
+    /// Remove identical patch operations within each output file
+    #[arg(long)]
+    dedupe: bool,
+    /// Only process files modified after this RFC 3339 timestamp or marker file's mtime
+    #[arg(long, value_name = "TIME|FILE", value_parser = cmd::generate::parse_since)]
+    since: Option<SystemTime>,
+    /// Only process files modified since the last run recorded in the output directory
+    #[arg(long, conflicts_with = "since")]
+    since_last: bool,
+    /// Write all patches into one file keyed by source path instead of a directory tree
+    #[arg(
+      long,
+      value_name = "PATH",
+      visible_alias = "output-single-file",
+      conflicts_with_all = ["since", "since_last"]
+    )]
+    single: Option<PathBuf>,
+    /// Report which regex produced each operation
+    #[arg(long)]
+    explain: bool,
+    /// Write a JSON report of the run (file counts, op counts, errors, elapsed time) to this path
+    #[arg(long, value_name = "PATH")]
+    report: Option<PathBuf>,
+    /// Follow symbolic links while walking the input directory
+    #[arg(long)]
+    follow_symlinks: bool,
+    /// Skip files or directories whose relative path matches this glob (repeatable)
+    #[arg(long, value_name = "GLOB")]
+    exclude: Vec<String>,
+    /// Skip input files larger than this many bytes (0 means unlimited)
+    #[arg(long, value_name = "BYTES", default_value_t = 32 * 1024 * 1024)]
+    max_file_size: u64,
+    /// Operation emitted for each translatable string
+    #[arg(long = "op", value_enum, default_value_t = OpKind::Replace)]
+    op_kind: OpKind,
+    /// Format of the written patch files ("yaml" appends ".yaml" to file names)
+    #[arg(long, value_enum, default_value_t = OutputFormat::Json)]
+    format: OutputFormat,
+    /// Sort operations in each file by path (batches by their first operation's path)
+    #[arg(long)]
+    sort_ops: bool,
+    /// Whether ".patch" is appended to non-patch file names or replaces their extension
+    #[arg(long, value_enum, default_value_t = PatchNaming::Append)]
+    naming: PatchNaming,
+    /// Convert line endings in generated string values
+    #[arg(long = "normalize-newlines", value_enum, default_value_t = NewlineMode::Keep)]
+    newlines: NewlineMode,
+    /// Check that every generated operation is well-formed before writing it
+    #[arg(long)]
+    self_check: bool,
+    /// Sort the keys of every object in the output alphabetically (arrays keep their order)
+    #[arg(long)]
+    sort_keys: bool,
+    /// Translation marker prefix, overriding every prefix and template in the config
+    #[arg(long)]
+    prefix: Option<String>,
+    /// Print a unified diff of each existing output file that changes (before writing it)
+    #[arg(long, visible_alias = "diff-output")]
+    show_diff: bool,
+    /// Indentation of JSON output: a number of spaces or "tab"
+    #[arg(long, value_name = "N|tab", default_value = "2", value_parser = cmd::generate::parse_indent)]
+    indent: Indent,
+    /// Prefix every operation's pointer with the source file's relative path (with --single only)
+    #[arg(long, requires = "single")]
+    relative_pointer: bool,
+    /// Allow the output directory to overlap the input directory, skipping previously generated files
+    #[arg(long)]
+    allow_overlap: bool,
+    /// Also process hidden files and directories (names starting with '.'), skipped by default
+    #[arg(long)]
+    include_hidden: bool,
+    /// Base Mod directory: emit "add" for paths missing from the corresponding base file, "replace" otherwise
+    #[arg(long = "base", value_name = "DIR", conflicts_with = "op_kind")]
+    base_dir: Option<PathBuf>,
+    /// Stop processing further files once this many seconds have elapsed, write the partial output and exit with an error
+    #[arg(long, value_name = "SECONDS", value_parser = cmd::generate::parse_time_budget)]
+    time_budget: Option<Duration>,
   },
-  /// Initialize configuration files (in executable's directory)
+  /// Initialize configuration files (in executable's directory by default)
   Init {
     /// Overwrite existing config files
     #[arg(short, long)]
     force: bool,
+    /// Write minimal config templates instead of the full defaults
+    #[arg(short, long)]
+    minimal: bool,
+    /// Print the config templates to stdout instead of writing them
+    #[arg(short, long, conflicts_with_all = ["force", "output_dir"])]
+    list: bool,
+    /// Directory to write config files to (defaults to the executable's directory)
+    #[arg(short, long, visible_alias = "config-dir")]
+    output_dir: Option<PathBuf>,
+  },
+  /// Check that all input files parse, without generating anything
+  Validate {
+    /// Input directory (Mod folder)
+    #[arg(short, long)]
+    input: PathBuf,
+    /// Directory containing the config files (defaults to the executable's directory)
+    #[arg(long)]
+    config_dir: Option<PathBuf>,
+  },
+  /// Strip the translation marker from translated patch files
+  Apply {
+    /// Directory containing the translated patch files
+    #[arg(short, long)]
+    translations: PathBuf,
+    /// Output directory
+    #[arg(short, long)]
+    output: PathBuf,
+    /// Directory containing the config files (defaults to the executable's directory)
+    #[arg(long)]
+    config_dir: Option<PathBuf>,
+  },
+  /// Export translatable strings to a gettext PO file
+  Extract {
+    /// Input directory (Mod folder)
+    #[arg(short, long)]
+    input: PathBuf,
+    /// Output PO file
+    #[arg(short, long)]
+    output: PathBuf,
+    /// Directory containing the config files (defaults to the executable's directory)
+    #[arg(long)]
+    config_dir: Option<PathBuf>,
+  },
+  /// Compare a fresh generation against an existing output directory without writing
+  Diff {
+    /// Input directory (Mod folder)
+    #[arg(short, long)]
+    input: PathBuf,
+    /// Existing output directory to compare against
+    #[arg(short, long)]
+    output: PathBuf,
+    /// Directory containing the config files (defaults to the executable's directory)
+    #[arg(long)]
+    config_dir: Option<PathBuf>,
+  },
+  /// List translatable strings that already carry the translation marker
+  Lint {
+    /// Input directory (Mod folder)
+    #[arg(short, long)]
+    input: PathBuf,
+    /// Directory containing the config files (defaults to the executable's directory)
+    #[arg(long)]
+    config_dir: Option<PathBuf>,
+  },
+  /// Count translatable strings in a Mod folder without writing anything
+  Stats {
+    /// Input directory (Mod folder)
+    #[arg(short, long)]
+    input: PathBuf,
+    /// Directory containing the config files (defaults to the executable's directory)
+    #[arg(long)]
+    config_dir: Option<PathBuf>,
+  },
+  /// Merge several generated template directories into one
+  Merge {
+    /// Input directories (generated template folders), later ones are appended after earlier ones
+    #[arg(short, long, required = true, num_args = 1..)]
+    inputs: Vec<PathBuf>,
+    /// Output directory
+    #[arg(short, long)]
+    output: PathBuf,
+  },
+  /// Export translatable strings to a CSV file for spreadsheet translation
+  ExportCsv {
+    /// Input directory (Mod folder)
+    #[arg(short, long)]
+    input: PathBuf,
+    /// Output CSV file
+    #[arg(short, long)]
+    output: PathBuf,
+    /// Directory containing the config files (defaults to the executable's directory)
+    #[arg(long)]
+    config_dir: Option<PathBuf>,
+  },
+  /// Build patch files from a translated CSV file (as written by export-csv)
+  ImportCsv {
+    /// Translated CSV file
+    #[arg(short, long)]
+    csv: PathBuf,
+    /// Output directory
+    #[arg(short, long)]
+    output: PathBuf,
+  },
+  /// Check that the config files parse and every regex compiles
+  CheckConfig {
+    /// Directory containing the config files (defaults to the executable's directory)
+    #[arg(long)]
+    config_dir: Option<PathBuf>,
+    /// Input directory (Mod folder), used to look up a project config like the other commands
+    #[arg(short, long)]
+    input: Option<PathBuf>,
+  },
+  /// Print a shell completion script to stdout
+  Completions {
+    /// Shell to generate the script for
+    #[arg(value_enum)]
+    shell: Shell,
   },
 }
 
 fn main() -> Result<()> {
   let cli = Cli::parse();
+  log::set_level(log::level_from_flags(cli.verbose, cli.quiet));
 
   match cli.command {
     Commands::Gen {
       input,
       output,
+      stdin,
+      ext,
       test,
-    } => cmd::generate::run(input, output, test),
-    Commands::Init { force } => cmd::init::run(force),
+      test_value,
+      max_ops_per_file,
+      max_ops_action,
+      capture_comments,
+      annotate,
+      dedupe_across_files,
+      roundtrip_check,
+      numeric_keys,
+      with_reverse,
+      sample,
+      sample_seed,
+      include_blank,
+      strict,
+      ignore_errors,
+      config_dir,
+      dry_run,
+      dedupe,
+      since,
+      since_last,
+      single,
+      explain,
+      report,
+      follow_symlinks,
+      exclude,
+      max_file_size,
+      op_kind,
+      format,
+      sort_ops,
+      naming,
+      newlines,
+      self_check,
+      sort_keys,
+      prefix,
+      show_diff,
+      indent,
+      relative_pointer,
+      allow_overlap,
+      include_hidden,
+      base_dir,
+      time_budget,
+    } => {
+      let options = GenOptions {
+        gen_test: test,
+        test_value,
+        max_ops_per_file,
+        max_ops_action,
+        capture_comments,
+        annotate,
+        dedupe_across_files,
+        roundtrip_check,
+        numeric_key_policy: numeric_keys,
+        reverse_dir: with_reverse,
+        sample,
+        sample_seed,
+        include_blank,
+        strict,
+        ignore_errors,
+        config_dir,
+        dry_run,
+        dedupe,
+        since,
+        since_last,
+        single_file: single,
+        explain,
+        report,
+        follow_symlinks,
+        exclude,
+        max_file_size,
+        op_kind,
+        format,
+        sort_ops,
+        naming,
+        newlines,
+        self_check,
+        sort_keys,
+        prefix,
+        show_diff,
+        indent,
+        relative_pointer,
+        allow_overlap,
+        include_hidden,
+        base_dir,
+        time_budget,
+      };
+      match (stdin, ext, input, output) {
+        (true, Some(ext), _, _) => cmd::generate::run_stdin(&ext, options),
+        (_, _, Some(input), Some(output)) => cmd::generate::run(input, output, options),
+        // clap 已保证参数组合有效
+        _ => unreachable!(),
+      }
+    }
+    Commands::Init {
+      force,
+      minimal,
+      list,
+      output_dir,
+    } => cmd::init::run(force, minimal, list, output_dir),
+    Commands::Validate { input, config_dir } => cmd::validate::run(input, config_dir),
+    Commands::Apply {
+      translations,
+      output,
+      config_dir,
+    } => cmd::apply::run(translations, output, config_dir),
+    Commands::Extract {
+      input,
+      output,
+      config_dir,
+    } => cmd::extract::run(input, output, config_dir),
+    Commands::Diff {
+      input,
+      output,
+      config_dir,
+    } => cmd::diff::run(input, output, config_dir),
+    Commands::Lint { input, config_dir } => cmd::lint::run(input, config_dir),
+    Commands::Stats { input, config_dir } => cmd::stats::run(input, config_dir),
+    Commands::Merge { inputs, output } => cmd::merge::run(inputs, output),
+    Commands::ExportCsv {
+      input,
+      output,
+      config_dir,
+    } => cmd::export_csv::run(input, output, config_dir),
+    Commands::ImportCsv { csv, output } => cmd::import_csv::run(csv, output),
+    Commands::CheckConfig { config_dir, input } => cmd::check_config::run(config_dir, input),
+    Commands::Completions { shell } => cmd::completions::run(shell, &mut Cli::command()),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn single_conflicts_with_incremental_runs() {
+    let args = [
+      "neki_lang",
+      "gen",
+      "-i",
+      "in",
+      "-o",
+      "out",
+      "--single",
+      "all.patch",
+    ];
+    assert!(Cli::try_parse_from(args).is_ok());
+    for extra in [&["--since-last"][..], &["--since", "2024-01-01T00:00:00Z"]] {
+      let error = Cli::try_parse_from(args.iter().chain(extra)).err().unwrap();
+      assert_eq!(error.kind(), clap::error::ErrorKind::ArgumentConflict);
+    }
   }
 }
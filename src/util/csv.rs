@@ -0,0 +1,106 @@
+use anyhow::{Result, bail};
+
+/// 按 RFC 4180 生成一行 CSV（以 CRLF 结尾）
+/// 含逗号、双引号或换行的字段用双引号包裹，字段内的双引号写为两个双引号
+pub fn write_record<S: AsRef<str>>(fields: &[S]) -> String {
+  let mut record = fields
+    .iter()
+    .map(|field| escape_field(field.as_ref()))
+    .collect::<Vec<_>>()
+    .join(",");
+  record.push_str("\r\n");
+  record
+}
+
+/// 转义单个字段
+fn escape_field(field: &str) -> String {
+  if field.contains([',', '"', '\r', '\n']) {
+    format!("\"{}\"", field.replace('"', "\"\""))
+  } else {
+    field.to_string()
+  }
+}
+
+/// 按 RFC 4180 解析 CSV 文本，返回所有记录
+/// 同时接受 CRLF 和 LF 换行，忽略开头的 UTF-8 BOM 和末尾的空行
+pub fn parse(text: &str) -> Result<Vec<Vec<String>>> {
+  let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+  let mut records = Vec::new();
+  let mut record = Vec::new();
+  let mut field = String::new();
+  let mut chars = text.chars().peekable();
+  let mut line = 1;
+
+  while let Some(ch) = chars.next() {
+    match ch {
+      // 带引号的字段只能从字段开头开始
+      '"' if field.is_empty() => {
+        let start_line = line;
+        loop {
+          match chars.next() {
+            Some('"') if chars.peek() == Some(&'"') => {
+              chars.next();
+              field.push('"');
+            }
+            Some('"') => break,
+            Some(ch) => {
+              if ch == '\n' {
+                line += 1;
+              }
+              field.push(ch);
+            }
+            None => bail!("Unterminated quoted field starting at line {}", start_line),
+          }
+        }
+        if !matches!(chars.peek(), None | Some(',') | Some('\r') | Some('\n')) {
+          bail!("Unexpected character after quoted field at line {}", line);
+        }
+      }
+      ',' => record.push(std::mem::take(&mut field)),
+      '\r' if chars.peek() == Some(&'\n') => {}
+      '\n' => {
+        record.push(std::mem::take(&mut field));
+        records.push(std::mem::take(&mut record));
+        line += 1;
+      }
+      _ => field.push(ch),
+    }
+  }
+  if !field.is_empty() || !record.is_empty() {
+    record.push(field);
+    records.push(record);
+  }
+
+  // 去掉空行
+  records.retain(|record| !(record.len() == 1 && record[0].is_empty()));
+  Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn records_round_trip() {
+    let fields = ["plain", "with,comma", "with \"quotes\"", "multi\nline", ""];
+    let text = write_record(&fields);
+    assert_eq!(
+      text,
+      "plain,\"with,comma\",\"with \"\"quotes\"\"\",\"multi\nline\",\r\n"
+    );
+    assert_eq!(parse(&text).unwrap(), [fields.map(String::from).to_vec()]);
+  }
+
+  #[test]
+  fn accepts_lf_bom_and_blank_lines() {
+    let records = parse("\u{feff}a,b\n\nc,d\n\n").unwrap();
+    assert_eq!(records, [["a", "b"], ["c", "d"]]);
+    assert_eq!(parse("a,b").unwrap(), [["a", "b"]]);
+  }
+
+  #[test]
+  fn rejects_malformed_quotes() {
+    assert!(parse("\"open,field\n").is_err());
+    assert!(parse("\"closed\"x,y\n").is_err());
+  }
+}
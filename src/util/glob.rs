@@ -0,0 +1,251 @@
+use std::path::Path;
+
+use anyhow::Result;
+use regex::RegexSet;
+use walkdir::WalkDir;
+
+/// 文件夹白名单
+/// 不含通配符（'*'、'?'、'['）的条目按路径前缀匹配，
+/// 含通配符的条目按 glob 匹配，匹配到的目录及其下所有文件都会被包含
+#[derive(Debug)]
+pub struct DirWhitelist {
+  /// 普通的路径前缀
+  prefixes: Vec<String>,
+  /// 由 glob 编译成的正则
+  globs: Option<RegexSet>,
+  /// 原始的 glob 条目，与 globs 中的正则一一对应
+  glob_entries: Vec<String>,
+}
+
+impl DirWhitelist {
+  /// 编译白名单条目，路径分隔符统一为 '/'
+  pub fn new<'a>(entries: impl IntoIterator<Item = &'a String>) -> Result<Self> {
+    let mut prefixes = Vec::new();
+    let mut glob_patterns = Vec::new();
+    let mut glob_entries = Vec::new();
+    for entry in entries {
+      let entry = normalize_separators(entry);
+      let entry = entry.trim_end_matches('/');
+      if is_glob(entry) {
+        // 匹配目录本身或其下的任意路径
+        glob_patterns.push(format!("^{}(?:/.*)?$", glob_to_regex(entry)));
+        glob_entries.push(entry.to_string());
+      } else {
+        prefixes.push(entry.to_string());
+      }
+    }
+    let globs = if glob_patterns.is_empty() {
+      None
+    } else {
+      Some(RegexSet::new(glob_patterns)?)
+    };
+    Ok(Self {
+      prefixes,
+      globs,
+      glob_entries,
+    })
+  }
+
+  /// 返回在输入目录中匹配不到任何路径的条目
+  /// 普通条目检查对应路径是否存在，glob 条目检查输入目录中是否有匹配的路径
+  pub fn missing_entries(&self, input_dir: &Path) -> Vec<String> {
+    let mut missing: Vec<String> = self
+      .prefixes
+      .iter()
+      .filter(|prefix| !prefix.is_empty() && !input_dir.join(prefix.as_str()).exists())
+      .cloned()
+      .collect();
+    if let Some(set) = &self.globs {
+      let mut matched = vec![false; self.glob_entries.len()];
+      for entry in WalkDir::new(input_dir).min_depth(1).into_iter().flatten() {
+        let relative_path = entry.path().strip_prefix(input_dir).unwrap_or(entry.path());
+        let path = normalize_separators(&relative_path.to_string_lossy());
+        for index in set.matches(&path) {
+          matched[index] = true;
+        }
+        if matched.iter().all(|&x| x) {
+          break;
+        }
+      }
+      missing.extend(
+        self
+          .glob_entries
+          .iter()
+          .zip(matched)
+          .filter(|(_, matched)| !matched)
+          .map(|(entry, _)| entry.clone()),
+      );
+    }
+    missing
+  }
+
+  /// 检查相对于输入目录的路径是否在白名单内
+  pub fn is_match(&self, relative_path: &Path) -> bool {
+    let path = normalize_separators(&relative_path.to_string_lossy());
+    // 按路径分段比较前缀，"item" 不会匹配 "items/..."
+    let prefix_match = self.prefixes.iter().any(|prefix| {
+      prefix.is_empty()
+        || path == *prefix
+        || path
+          .strip_prefix(prefix.as_str())
+          .is_some_and(|rest| rest.starts_with('/'))
+    });
+    prefix_match || self.globs.as_ref().is_some_and(|set| set.is_match(&path))
+  }
+}
+
+/// 排除列表：相对路径匹配任一 glob 的文件或目录（及其下所有文件）会被排除
+#[derive(Debug)]
+pub struct ExcludeGlobs {
+  globs: Option<RegexSet>,
+}
+
+impl ExcludeGlobs {
+  /// 编译排除条目，路径分隔符统一为 '/'
+  pub fn new<'a>(entries: impl IntoIterator<Item = &'a String>) -> Result<Self> {
+    let glob_patterns: Vec<String> = entries
+      .into_iter()
+      .map(|entry| {
+        let entry = normalize_separators(entry);
+        format!("^{}(?:/.*)?$", glob_to_regex(entry.trim_end_matches('/')))
+      })
+      .collect();
+    let globs = if glob_patterns.is_empty() {
+      None
+    } else {
+      Some(RegexSet::new(glob_patterns)?)
+    };
+    Ok(Self { globs })
+  }
+
+  /// 检查相对于输入目录的路径是否被排除
+  pub fn is_match(&self, relative_path: &Path) -> bool {
+    let path = normalize_separators(&relative_path.to_string_lossy());
+    self.globs.as_ref().is_some_and(|set| set.is_match(&path))
+  }
+}
+
+/// 转义 glob 通配符，使条目只匹配字面路径
+pub fn escape(path: &str) -> String {
+  let mut escaped = String::with_capacity(path.len());
+  for c in path.chars() {
+    match c {
+      '*' | '?' | '[' => escaped.push_str(&format!("[{}]", c)),
+      _ => escaped.push(c),
+    }
+  }
+  escaped
+}
+
+/// 统一使用 '/' 作为路径分隔符
+fn normalize_separators(path: &str) -> String {
+  path.replace('\\', "/")
+}
+
+/// 检查条目是否包含 glob 通配符
+fn is_glob(entry: &str) -> bool {
+  entry.contains(['*', '?', '['])
+}
+
+/// 把 glob 转换为正则（不含首尾锚点）
+/// "**/" 匹配任意层目录（包括零层），"**" 匹配任意字符，
+/// "*" 匹配单段内的任意字符，"?" 匹配单段内的单个字符，"[...]" 为字符类
+pub fn glob_to_regex(glob: &str) -> String {
+  let mut regex = String::new();
+  let chars: Vec<char> = glob.chars().collect();
+  let mut i = 0;
+  while i < chars.len() {
+    match chars[i] {
+      '*' if chars.get(i + 1) == Some(&'*') => {
+        if chars.get(i + 2) == Some(&'/') {
+          regex.push_str("(?:.*/)?");
+          i += 3;
+        } else {
+          regex.push_str(".*");
+          i += 2;
+        }
+        continue;
+      }
+      '*' => regex.push_str("[^/]*"),
+      '?' => regex.push_str("[^/]"),
+      '[' => match chars[i + 1..].iter().position(|&c| c == ']') {
+        Some(len) => {
+          let class: String = chars[i + 1..i + 1 + len].iter().collect();
+          let class = match class.strip_prefix('!') {
+            Some(rest) => format!("^{}", rest),
+            None => class,
+          };
+          // '[' 在正则的字符类中有特殊含义，需要转义
+          regex.push_str(&format!(
+            "[{}]",
+            class.replace('\\', "\\\\").replace('[', "\\[")
+          ));
+          i += len + 2;
+          continue;
+        }
+        // 没有闭合的 ']'，按普通字符处理
+        None => regex.push_str("\\["),
+      },
+      c => regex.push_str(&regex::escape(&c.to_string())),
+    }
+    i += 1;
+  }
+  regex
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn globs_match(glob: &str, path: &str) -> bool {
+    regex::Regex::new(&format!("^{}$", glob_to_regex(glob)))
+      .unwrap()
+      .is_match(path)
+  }
+
+  #[test]
+  fn glob_wildcards() {
+    assert!(globs_match("items/*.item", "items/sword.item"));
+    assert!(!globs_match("items/*.item", "items/weapons/sword.item"));
+    assert!(globs_match("items/**/*.item", "items/sword.item"));
+    assert!(globs_match(
+      "items/**/*.item",
+      "items/weapons/melee/sword.item"
+    ));
+    assert!(globs_match("items/**", "items/a/b"));
+    assert!(globs_match("a?c", "abc"));
+    assert!(!globs_match("a?c", "a/c"));
+    assert!(globs_match("[ab].txt", "b.txt"));
+    assert!(!globs_match("[!ab].txt", "b.txt"));
+    assert!(globs_match("a[.txt", "a[.txt"));
+    assert!(globs_match("a+b(c).txt", "a+b(c).txt"));
+  }
+
+  #[test]
+  fn escaped_paths_match_literally() {
+    let path = "weird[1]/a*b?.txt";
+    assert!(globs_match(&escape(path), path));
+    assert!(!globs_match(&escape(path), "weird1/axb?.txt"));
+  }
+
+  #[test]
+  fn whitelist_matches_whole_segments() {
+    let entries = ["items".to_string(), "objects/*/crafting".to_string()];
+    let whitelist = DirWhitelist::new(&entries).unwrap();
+    assert!(whitelist.is_match(Path::new("items/sword.item")));
+    assert!(whitelist.is_match(Path::new("items")));
+    assert!(!whitelist.is_match(Path::new("items2/sword.item")));
+    assert!(whitelist.is_match(Path::new("objects/wooden/crafting/table.object")));
+    assert!(!whitelist.is_match(Path::new("objects/wooden/table.object")));
+  }
+
+  #[test]
+  fn exclude_globs_cover_directories() {
+    let entries = ["**/test".to_string(), "*.bak".to_string()];
+    let exclude = ExcludeGlobs::new(&entries).unwrap();
+    assert!(exclude.is_match(Path::new("items/test/a.item")));
+    assert!(exclude.is_match(Path::new("old.bak")));
+    assert!(!exclude.is_match(Path::new("items/old.bak")));
+    assert!(!ExcludeGlobs::new(&[]).unwrap().is_match(Path::new("a")));
+  }
+}
@@ -1,7 +1,10 @@
-use std::{error::Error, fmt};
+use std::{collections::HashMap, error::Error, fmt};
 
+use serde::de::DeserializeOwned;
 use serde_json::Value;
 
+use crate::util::json_patch::escape_pointer_token;
+
 const WS: [char; 8] = [
   ' ', '\t', '\r', '\n', '\u{000B}', // \v
   '\u{000C}', // \f
@@ -28,6 +31,14 @@ fn escapee_get(esc: char) -> Option<&'static str> {
 #[derive(Debug)]
 pub struct ParseError {
   pub message: String,
+  /// Character index of the offending character
+  pub index: usize,
+  /// Line number of the offending character (1-based)
+  pub line: usize,
+  /// Column number of the offending character
+  pub column: usize,
+  /// Character range `(start, end)` covered by the error, `None` at end of input
+  pub span: Option<(usize, usize)>,
 }
 
 impl fmt::Display for ParseError {
@@ -40,6 +51,52 @@ impl Error for ParseError {}
 
 type ParseResult<T> = Result<T, ParseError>;
 
+/// How to handle a key that appears more than once in the same object
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum DuplicateKeyPolicy {
+  /// Keep the last value (same as plain JSON parsers)
+  #[default]
+  LastWins,
+  /// Keep the first value and ignore the later ones
+  FirstWins,
+  /// Fail with an error pointing at the duplicate key
+  Error,
+}
+
+/// Default maximum nesting depth of arrays and objects
+pub const DEFAULT_MAX_DEPTH: usize = 256;
+
+/// Options controlling how the parser treats non-standard input
+#[derive(Debug, Clone)]
+pub struct ParseOptions {
+  pub duplicate_keys: DuplicateKeyPolicy,
+  /// Maximum nesting depth of arrays and objects, deeper input is rejected
+  /// instead of overflowing the stack
+  pub max_depth: usize,
+  /// Accept `\xNN` escapes in strings, read as the codepoint U+00NN
+  pub allow_hex_escapes: bool,
+  /// Reject JSON5 extensions and accept only strict RFC 8259 JSON
+  pub strict: bool,
+  /// Width of a tab stop when counting columns in error positions,
+  /// 1 counts a tab as a single column
+  pub tab_width: usize,
+}
+
+impl Default for ParseOptions {
+  fn default() -> Self {
+    Self {
+      duplicate_keys: DuplicateKeyPolicy::default(),
+      max_depth: DEFAULT_MAX_DEPTH,
+      allow_hex_escapes: false,
+      strict: false,
+      tab_width: 1,
+    }
+  }
+}
+
+/// Position in the input: (index, line, column)
+type Position = (usize, usize, usize);
+
 pub struct Parser {
   /// The index of the current character
   at: usize,
@@ -51,6 +108,22 @@ pub struct Parser {
   ch: Option<char>,
   /// The input text，store as vector of chars for faster access
   text: Vec<char>,
+  /// Whether to retain comments instead of discarding them
+  capture_comments: bool,
+  /// Comments read but not yet attached to a value
+  pending_comments: Vec<String>,
+  /// Line on which the last pending comment ends
+  last_comment_line: usize,
+  /// Line on which the last member value ends
+  last_value_line: usize,
+  /// Keys/indices leading to the value currently being parsed
+  path: Vec<String>,
+  /// Captured comments, keyed by the JSON pointer of the following value
+  comments: HashMap<String, String>,
+  /// Parse options
+  options: ParseOptions,
+  /// Current nesting depth of arrays and objects
+  depth: usize,
 }
 
 impl Parser {
@@ -58,49 +131,150 @@ impl Parser {
     Self {
       at: 0,
       line_number: 1,
-      column_number: 1,
+      // 初始的占位字符不计入列号，第一个字符为第1列
+      column_number: 0,
       ch: Some(' '),
       text: input_str.chars().collect(),
+      capture_comments: false,
+      pending_comments: Vec::new(),
+      last_comment_line: 0,
+      last_value_line: 0,
+      path: Vec::new(),
+      comments: HashMap::new(),
+      options: ParseOptions::default(),
+      depth: 0,
+    }
+  }
+
+  /// Use the given parse options.
+  pub fn with_options(mut self, options: ParseOptions) -> Self {
+    self.options = options;
+    self
+  }
+
+  /// Parse a single value and stop right after it, leaving whatever
+  /// follows unconsumed. Use `remaining_index` to find where it stopped.
+  pub fn parse_value(&mut self) -> ParseResult<Value> {
+    self.value()
+  }
+
+  /// Character (not byte) index of the first character the parser has not
+  /// consumed, equal to the input length once everything is consumed.
+  pub fn remaining_index(&self) -> usize {
+    self.at.saturating_sub(1)
+  }
+
+  /// Current position, for reporting errors later.
+  fn position(&self) -> Position {
+    (self.at, self.line_number, self.column_number)
+  }
+
+  /// Retain comments and attach them to the value that follows.
+  pub fn with_comments(mut self) -> Self {
+    self.capture_comments = true;
+    self
+  }
+
+  /// Take the comments collected since the last value, if any.
+  fn take_pending_comment(&mut self) -> Option<String> {
+    if self.pending_comments.is_empty() {
+      return None;
     }
+    let comment = self.pending_comments.join("\n");
+    self.pending_comments.clear();
+    Some(comment)
+  }
+
+  /// Discard pending comments separated from the current character by a
+  /// blank line, so only comments immediately preceding a member attach to it.
+  fn drop_detached_comments(&mut self) {
+    if self.line_number > self.last_comment_line + 1 {
+      self.pending_comments.clear();
+    }
+  }
+
+  /// Parse a member value at `key`, attaching any leading comment to its pointer.
+  fn member_value(&mut self, key: &str) -> ParseResult<Value> {
+    if !self.capture_comments {
+      return self.value();
+    }
+    let comment = self.take_pending_comment();
+    self.path.push(escape_pointer_token(key));
+    if let Some(comment) = comment {
+      self
+        .comments
+        .insert(format!("/{}", self.path.join("/")), comment);
+    }
+    let value = self.value();
+    self.path.pop();
+    self.last_value_line = self.line_number;
+    value
   }
 
   fn error(&self, msg: String) -> ParseError {
-    let start = self.at.saturating_sub(1);
-    let end = (self.at + 19).min(self.text.len());
+    self.error_at(msg, self.position())
+  }
+
+  /// Error for a JSON5 extension used while parsing strict JSON
+  fn strict_error(&self, what: &str) -> ParseError {
+    self.error(format!("{} not allowed in strict JSON", what))
+  }
+
+  /// Build an error at `position`; the span runs up to the current character.
+  fn error_at(&self, msg: String, (at, line_number, column_number): Position) -> ParseError {
+    // `at` points past the character in `ch`
+    let index = at.saturating_sub(1);
+    let start = index.min(self.text.len());
+    let end = (at + 19).min(self.text.len());
     let snippet: String = self.text[start..end].iter().collect();
     let snippet_json = serde_json::to_string(&snippet).unwrap();
 
+    let span = (index < self.text.len()).then(|| {
+      let span_end = self.at.saturating_sub(1).max(index + 1);
+      (index, span_end.min(self.text.len()))
+    });
+
     ParseError {
       message: format!(
         "{} at line {} column {}. Next part: {}",
-        msg, self.line_number, self.column_number, snippet_json
+        msg, line_number, column_number, snippet_json
       ),
+      index,
+      line: line_number,
+      column: column_number,
+      span,
     }
   }
 
   fn next(&mut self, expect: Option<char>) -> ParseResult<Option<char>> {
     // 如果有期望字符，检查当前字符是否匹配
-    if let Some(c) = expect {
-      if self.ch != Some(c) {
-        return Err(self.error(format!(
-          "Expected {} instead of {}",
-          render_char(c),
-          self.ch.map_or("EOF".to_string(), |x| render_char(x))
-        )));
-      }
+    if let Some(c) = expect
+      && self.ch != Some(c)
+    {
+      return Err(self.error(format!(
+        "Expected {} instead of {}",
+        render_char(Some(c)),
+        render_char(self.ch)
+      )));
     }
 
     // 获取下一个字符
+    let prev = self.ch;
     self.ch = self.text.get(self.at).copied();
     self.at += 1;
-    self.column_number += 1;
 
-    // 处理换行
-    if let Some(ch) = self.ch {
-      if ch == '\n' || (ch == '\r' && self.peek() != Some('\n')) {
-        self.line_number += 1;
-        self.column_number = 0;
-      }
+    // 上一个字符是换行时，当前字符位于新一行的第1列
+    if let Some(prev) = prev
+      && (prev == '\n' || (prev == '\r' && self.ch != Some('\n')))
+    {
+      self.line_number += 1;
+      self.column_number = 1;
+    } else if prev == Some('\t') {
+      // 跳到下一个制表位
+      let tab_width = self.options.tab_width.max(1);
+      self.column_number = (self.column_number.saturating_sub(1) / tab_width + 1) * tab_width + 1;
+    } else {
+      self.column_number += 1;
     }
 
     Ok(self.ch)
@@ -120,25 +294,32 @@ impl Parser {
     let mut is_float = false;
 
     // 处理正负号
-    if let Some(ch) = self.ch {
-      if ch == '-' || ch == '+' {
-        if ch == '-' {
-          sign = -1.0;
-        }
-        self.next(Some(ch))?;
+    if self.options.strict && self.ch == Some('+') {
+      return Err(self.strict_error("Leading '+'"));
+    }
+    if let Some(ch) = self.ch
+      && (ch == '-' || ch == '+')
+    {
+      if ch == '-' {
+        sign = -1.0;
       }
+      self.next(Some(ch))?;
+    }
+
+    if self.options.strict && matches!(self.ch, Some('I' | 'N')) {
+      return Err(self.strict_error("Infinity and NaN are"));
     }
 
     // 处理 Infinity
     if self.ch == Some('I') {
       let val = self.word()?;
-      if let Value::String(ref s) = val {
-        if s == "Infinity" {
-          return match serde_json::Number::from_f64(sign * f64::INFINITY) {
-            Some(num) => Ok(Value::Number(num)),
-            None => Err(self.error("Bad number".to_string())),
-          };
-        }
+      if let Value::String(ref s) = val
+        && s == "Infinity"
+      {
+        return match serde_json::Number::from_f64(sign * f64::INFINITY) {
+          Some(num) => Ok(Value::Number(num)),
+          None => Err(self.error("Bad number".to_string())),
+        };
       }
       return Err(self.error("Unexpected word for number".to_string()));
     }
@@ -163,6 +344,9 @@ impl Parser {
       self.next(None)?;
       if let Some(ch) = self.ch {
         if ch == 'x' || ch == 'X' {
+          if self.options.strict {
+            return Err(self.strict_error("Hexadecimal number"));
+          }
           string.push(ch);
           self.next(None)?;
           base = 16;
@@ -183,19 +367,31 @@ impl Parser {
             break;
           }
         }
-        // 小数部分
+        // 小数部分，允许省略整数部分（.5）或小数部分（5.），但不能都省略
         if self.ch == Some('.') {
           is_float = true;
+          let has_int_part = !string.is_empty();
+          if !has_int_part {
+            string.push('0');
+          }
           string.push('.');
           self.next(None)?;
+          let mut has_frac_part = false;
           while let Some(ch) = self.ch {
-            if ch.is_digit(10) {
+            if ch.is_ascii_digit() {
+              has_frac_part = true;
               string.push(ch);
               self.next(None)?;
             } else {
               break;
             }
           }
+          if !has_int_part && !has_frac_part {
+            return Err(self.error("Bad number: no digits around decimal point".to_string()));
+          }
+          if self.options.strict && !(has_int_part && has_frac_part) {
+            return Err(self.strict_error("Number without digits on both sides of '.'"));
+          }
         }
         // 指数部分
         if let Some(ch) = self.ch {
@@ -229,17 +425,44 @@ impl Parser {
             break;
           }
         }
+        // Hex floats such as 0x1.8p3 are not valid JSON5
+        if matches!(self.ch, Some('.' | 'p' | 'P')) {
+          return Err(
+            self.error("Hexadecimal floating-point literals are not allowed in JSON5".to_string()),
+          );
+        }
       }
       _ => {}
     }
 
+    // 整数直接按整数解析，避免经过 f64 丢失精度（如超过 2^53 的 ID）
+    // 超出 i64/u64 范围时仍回退到 f64
+    if !is_float {
+      let digits = string.trim_start_matches("0x").trim_start_matches("0X");
+      let signed_digits = if sign < 0.0 {
+        format!("-{}", digits)
+      } else {
+        digits.to_string()
+      };
+      if let Ok(int_val) = i128::from_str_radix(&signed_digits, base)
+        && let Some(num) = serde_json::Number::from_i128(int_val)
+      {
+        return Ok(Value::Number(num));
+      }
+    }
+
     // 转换为数字
     let number = if base == 16 {
-      // 跳过前缀 0x
-      match u64::from_str_radix(string.trim_start_matches("0x").trim_start_matches("0X"), 16) {
-        Ok(n) => n as f64 * sign,
-        Err(_) => return Err(self.error("Bad hex number".to_string())),
+      // 跳过前缀 0x，超出整数范围的十六进制数逐位累加为 f64
+      let digits = string.trim_start_matches("0x").trim_start_matches("0X");
+      if digits.is_empty() {
+        return Err(self.error("Bad hex number: no digits after 0x".to_string()));
       }
+      digits
+        .chars()
+        .filter_map(|c| c.to_digit(16))
+        .fold(0.0, |acc, digit| acc * 16.0 + digit as f64)
+        * sign
     } else {
       match string.parse::<f64>() {
         Ok(n) => n * sign,
@@ -275,7 +498,7 @@ impl Parser {
           Some(num) => Ok(Value::Number(num)),
           None => Err(self.error("Bad number".to_string())),
         }
-      } else if number >= 0.0 && number <= (u64::MAX as f64) {
+      } else if number >= 0.0 && number < (u64::MAX as f64) {
         let uint_val = number as u128;
         match serde_json::Number::from_u128(uint_val) {
           Some(num) => Ok(Value::Number(num)),
@@ -298,6 +521,9 @@ impl Parser {
       Some('"') | Some('\'') => self.ch.unwrap(),
       _ => return Err(self.error("Bad string: expected starting quote".to_string())),
     };
+    if self.options.strict && delim == '\'' {
+      return Err(self.strict_error("Single-quoted string"));
+    }
     let mut result = String::new();
 
     // 进入字符串内容
@@ -310,15 +536,23 @@ impl Parser {
         match self.ch {
           Some('u') => {
             // 处理 \uXXXX
-            let mut uffff = 0u32;
-            for _ in 0..4 {
+            let mut uffff = self.unicode_escape()?;
+            // 高位代理必须紧跟一个低位代理的 \uXXXX，二者组合成一个字符
+            if (0xD800..=0xDBFF).contains(&uffff) {
+              if self.peek() != Some('\\') || self.text.get(self.at + 1) != Some(&'u') {
+                return Err(self.error(
+                  "High surrogate not followed by a low surrogate escape in string".to_string(),
+                ));
+              }
               self.next(None)?;
-              let hex = self.ch.and_then(|c| c.to_digit(16));
-              if let Some(h) = hex {
-                uffff = uffff * 16 + h;
-              } else {
-                return Err(self.error("Invalid Unicode escape in string".to_string()));
+              self.next(Some('\\'))?;
+              let low = self.unicode_escape()?;
+              if !(0xDC00..=0xDFFF).contains(&low) {
+                return Err(self.error(
+                  "High surrogate not followed by a low surrogate escape in string".to_string(),
+                ));
               }
+              uffff = 0x10000 + ((uffff - 0xD800) << 10) + (low - 0xDC00);
             }
             if let Some(ch) = std::char::from_u32(uffff) {
               result.push(ch);
@@ -326,6 +560,14 @@ impl Parser {
               return Err(self.error("Invalid Unicode codepoint in string".to_string()));
             }
           }
+          Some('x') if self.options.allow_hex_escapes => {
+            // 处理 \xNN
+            let xff = self.hex_escape()?;
+            result.push(char::from(xff));
+          }
+          Some(esc) if self.options.strict && !"\"\\/bfnrt".contains(esc) => {
+            return Err(self.error(format!("Invalid escape character in strict JSON: {}", esc)));
+          }
           Some('\r') => {
             // 处理 \r\n 换行
             if self.peek() == Some('\n') {
@@ -342,6 +584,8 @@ impl Parser {
           }
           None => return Err(self.error("Unexpected end of input in string escape".to_string())),
         }
+      } else if self.options.strict && self.ch.is_some_and(|ch| ch < '\u{0020}') {
+        return Err(self.strict_error("Unescaped control character in string"));
       } else if self.ch == Some('\r') {
         // 跳过裸 \r
       } else if self.ch == Some('\n') {
@@ -356,46 +600,84 @@ impl Parser {
     Err(self.error("Bad string".to_string()))
   }
 
-  // 跳过单行注释
-  fn inline_comment(&mut self) -> ParseResult<()> {
+  /// Read the four hex digits of a `\uXXXX` escape, the current character
+  /// being the `u`.
+  fn unicode_escape(&mut self) -> ParseResult<u32> {
+    let mut uffff = 0u32;
+    for _ in 0..4 {
+      self.next(None)?;
+      let hex = self.ch.and_then(|c| c.to_digit(16));
+      if let Some(h) = hex {
+        uffff = uffff * 16 + h;
+      } else {
+        return Err(self.error("Invalid Unicode escape in string".to_string()));
+      }
+    }
+    Ok(uffff)
+  }
+
+  /// Read the two hex digits of a `\xNN` escape, the current character
+  /// being the `x`.
+  fn hex_escape(&mut self) -> ParseResult<u8> {
+    let mut xff = 0u8;
+    for _ in 0..2 {
+      self.next(None)?;
+      let hex = self.ch.and_then(|c| c.to_digit(16));
+      if let Some(h) = hex {
+        xff = xff * 16 + h as u8;
+      } else {
+        return Err(
+          self.error("Invalid hex escape in string: expected two hex digits after \\x".to_string()),
+        );
+      }
+    }
+    Ok(xff)
+  }
+
+  // 跳过单行注释，返回注释内容
+  fn inline_comment(&mut self) -> ParseResult<String> {
     if self.ch != Some('/') {
       return Err(self.error("Not an inline comment".to_string()));
     }
+    let mut content = String::new();
     loop {
       self.next(None)?;
       match self.ch {
         Some('\n') | Some('\r') => {
           self.next(None)?; // 跳过换行符
-          return Ok(());
+          return Ok(content);
         }
-        None => return Ok(()), // 文件结尾也算注释结束
-        _ => {}
+        None => return Ok(content), // 文件结尾也算注释结束
+        Some(ch) => content.push(ch),
       }
     }
   }
 
-  // 跳过多行注释
-  fn block_comment(&mut self) -> ParseResult<()> {
+  // 跳过多行注释，返回注释内容
+  fn block_comment(&mut self) -> ParseResult<String> {
     if self.ch != Some('*') {
       return Err(self.error("Not a block comment".to_string()));
     }
+    let mut content = String::new();
     loop {
       self.next(None)?;
       while self.ch == Some('*') {
         self.next(Some('*'))?;
         if self.ch == Some('/') {
           self.next(Some('/'))?;
-          return Ok(());
+          return Ok(content);
         }
+        content.push('*');
       }
-      if self.ch.is_none() {
-        return Err(self.error("Unterminated block comment".to_string()));
+      match self.ch {
+        Some(ch) => content.push(ch),
+        None => return Err(self.error("Unterminated block comment".to_string())),
       }
     }
   }
 
-  // 跳过注释（自动判断类型）
-  fn comment(&mut self) -> ParseResult<()> {
+  // 跳过注释（自动判断类型），返回注释内容
+  fn comment(&mut self) -> ParseResult<String> {
     if self.ch != Some('/') {
       return Err(self.error("Not a comment".to_string()));
     }
@@ -411,8 +693,28 @@ impl Parser {
   fn white(&mut self) -> ParseResult<()> {
     loop {
       match self.ch {
+        Some('/') if self.options.strict => return Err(self.strict_error("Comment")),
+        Some(c) if self.options.strict && WS.contains(&c) && !" \t\r\n".contains(c) => {
+          return Err(self.strict_error("Non-JSON whitespace"));
+        }
         Some('/') => {
-          self.comment()?;
+          let start_line = self.line_number;
+          let is_inline = self.text.get(self.at) == Some(&'/');
+          let content = self.comment()?;
+          // 保留注释内容，等待关联到下一个值；与前一条注释隔着空行时丢弃前一条
+          // 与上一个值在同一行开始的注释属于上一个值，直接丢弃
+          if self.capture_comments && start_line != self.last_value_line {
+            if start_line > self.last_comment_line + 1 {
+              self.pending_comments.clear();
+            }
+            self.pending_comments.push(content.trim().to_string());
+            // 单行注释已跳过行尾的换行符，结束行即开始行
+            self.last_comment_line = if is_inline {
+              start_line
+            } else {
+              self.line_number
+            };
+          }
         }
         Some(c) if WS.contains(&c) => {
           // 跳过空白字符
@@ -450,6 +752,7 @@ impl Parser {
         self.next(Some('l'))?;
         Ok(Value::Null)
       }
+      Some('I' | 'N') if self.options.strict => Err(self.strict_error("Infinity and NaN are")),
       Some('I') => {
         self.next(Some('I'))?;
         self.next(Some('n'))?;
@@ -467,13 +770,35 @@ impl Parser {
         self.next(Some('N'))?;
         Ok(Value::String("NaN".to_string()))
       }
-      _ => Err(self.error(format!(
-        "Unexpected {}",
-        self.ch.map_or("EOF".to_string(), |x| render_char(x))
-      ))),
+      _ => Err(self.error(format!("Unexpected {}", render_char(self.ch)))),
     }
   }
 
+  /// Parse an unquoted object key: an identifier starting with a letter,
+  /// `$` or `_`, continuing with alphanumerics, `$` or `_`.
+  fn identifier(&mut self) -> ParseResult<String> {
+    let mut key = String::new();
+
+    match self.ch {
+      Some(c) if c == '$' || c == '_' || c.is_alphabetic() => {
+        key.push(c);
+        self.next(None)?;
+      }
+      _ => return Err(self.error("Bad identifier as unquoted key".to_string())),
+    }
+
+    while let Some(c) = self.ch {
+      if c == '$' || c == '_' || c.is_alphanumeric() {
+        key.push(c);
+        self.next(None)?;
+      } else {
+        break;
+      }
+    }
+
+    Ok(key)
+  }
+
   fn array(&mut self) -> ParseResult<Value> {
     let mut arr = Vec::new();
     let mut had_comma = false;
@@ -483,11 +808,13 @@ impl Parser {
       self.next(Some('['))?;
       self.white()?;
       loop {
+        self.drop_detached_comments();
         match self.ch {
           Some(']') => {
             if had_comma {
               return Err(self.error("Superfluous trailing comma".to_string()));
             }
+            self.pending_comments.clear();
             self.next(Some(']'))?;
             return Ok(Value::Array(arr));
           }
@@ -495,7 +822,8 @@ impl Parser {
             return Err(self.error("Missing array element".to_string()));
           }
           Some(_) => {
-            arr.push(self.value()?);
+            let value = self.member_value(&arr.len().to_string())?;
+            arr.push(value);
           }
           None => break,
         }
@@ -512,6 +840,36 @@ impl Parser {
     Err(self.error("Bad array".to_string()))
   }
 
+  /// Parse the `: value` part of an object member and insert it,
+  /// applying the duplicate key policy.
+  fn member(
+    &mut self,
+    obj: &mut serde_json::Map<String, Value>,
+    key: String,
+    key_position: Position,
+  ) -> ParseResult<()> {
+    self.white()?;
+    self.next(Some(':'))?;
+    let value = self.member_value(&key)?;
+    if obj.contains_key(&key) {
+      match self.options.duplicate_keys {
+        DuplicateKeyPolicy::LastWins => {}
+        DuplicateKeyPolicy::FirstWins => return Ok(()),
+        DuplicateKeyPolicy::Error => {
+          return Err(self.error_at(
+            format!("Duplicate key {}", serde_json::to_string(&key).unwrap()),
+            key_position,
+          ));
+        }
+      }
+    }
+    obj.insert(key, value);
+    Ok(())
+  }
+
+  /// Parse an object. Members keep their source order (serde_json is built
+  /// with `preserve_order`), so patches generated from it follow the file's
+  /// layout. A duplicate key keeps the position of its first occurrence.
   fn object(&mut self) -> ParseResult<Value> {
     let mut obj = serde_json::Map::new();
     let mut had_comma = false;
@@ -520,33 +878,36 @@ impl Parser {
       self.next(Some('{'))?;
       self.white()?;
       loop {
+        self.drop_detached_comments();
         match self.ch {
           Some('}') => {
             if had_comma {
               return Err(self.error("Superfluous trailing comma".to_string()));
             }
+            self.pending_comments.clear();
             self.next(Some('}'))?;
             return Ok(Value::Object(obj));
           }
           Some('"') | Some('\'') => {
-            // 只允许带引号的key
+            // 带引号的key
+            let key_position = self.position();
             let key_val = self.string()?;
             let key = if let Value::String(s) = key_val {
               s
             } else {
               return Err(self.error("Object key must be a string".to_string()));
             };
-            self.white()?;
-            self.next(Some(':'))?;
-            let value = self.value()?;
-            obj.insert(key, value);
+            self.member(&mut obj, key, key_position)?;
           }
           Some(',') => {
             return Err(self.error("Expected key".to_string()));
           }
+          Some(_) if self.options.strict => return Err(self.strict_error("Unquoted key")),
           Some(_) => {
-            // 不允许未加引号的key
-            return Err(self.error("Unquoted key".to_string()));
+            // 未加引号的key（ECMAScript标识符）
+            let key_position = self.position();
+            let key = self.identifier()?;
+            self.member(&mut obj, key, key_position)?;
           }
           None => break,
         }
@@ -566,22 +927,34 @@ impl Parser {
   fn value(&mut self) -> ParseResult<Value> {
     self.white()?;
     match self.ch {
-      Some('{') => self.object(),
-      Some('[') => self.array(),
+      Some(c @ ('{' | '[')) => {
+        if self.depth >= self.options.max_depth {
+          return Err(self.error("Maximum nesting depth exceeded".to_string()));
+        }
+        self.depth += 1;
+        let result = if c == '{' {
+          self.object()
+        } else {
+          self.array()
+        };
+        self.depth -= 1;
+        result
+      }
       Some('"') | Some('\'') => self.string(),
       Some('-') | Some('+') | Some('.') => self.number(),
-      Some(c) if c.is_digit(10) => self.number(),
+      Some(c) if c.is_ascii_digit() => self.number(),
       _ => self.word(),
     }
   }
 }
 
-// 转义字符映射
-fn render_char(c: char) -> String {
-  if c == '\0' {
-    "EOF".to_string()
-  } else {
-    format!("'{}'", c)
+// 转义字符映射：None 表示输入结束，NUL 等控制字符按转义形式显示
+fn render_char(c: Option<char>) -> String {
+  match c {
+    None => "EOF".to_string(),
+    Some('\0') => "'\\0'".to_string(),
+    Some(c) if c.is_control() => format!("'{}'", c.escape_default()),
+    Some(c) => format!("'{}'", c),
   }
 }
 
@@ -595,3 +968,354 @@ pub fn parse(text: &str) -> ParseResult<Value> {
   }
   Ok(result)
 }
+
+// 对外接口：解析以空白分隔的多个连续值（如每行一个值），直到输入结束
+pub fn parse_many(text: &str) -> ParseResult<Vec<Value>> {
+  let mut parser = Parser::new(text);
+  let mut values = Vec::new();
+  parser.white()?;
+  while parser.ch.is_some() {
+    values.push(parser.value()?);
+    parser.white()?;
+  }
+  Ok(values)
+}
+
+// 对外接口：解析并反序列化为指定类型
+// 反序列化错误没有位置信息，此时 line 和 column 为 0
+pub fn from_str<T: DeserializeOwned>(text: &str) -> ParseResult<T> {
+  let value = parse(text)?;
+  T::deserialize(value).map_err(|e| ParseError {
+    message: format!("Invalid value: {}", e),
+    index: 0,
+    line: 0,
+    column: 0,
+    span: None,
+  })
+}
+
+// 对外接口：使用指定的解析选项
+pub fn parse_with_options(text: &str, options: ParseOptions) -> ParseResult<Value> {
+  let mut parser = Parser::new(text).with_options(options);
+  let result = parser.value()?;
+  parser.white()?;
+  if parser.ch.is_some() {
+    return Err(parser.error("Syntax error".to_string()));
+  }
+  Ok(result)
+}
+
+// 对外接口：只接受严格的 RFC 8259 JSON，拒绝注释、单引号字符串、无引号的键等 JSON5 扩展
+pub fn parse_strict(text: &str) -> ParseResult<Value> {
+  parse_with_options(
+    text,
+    ParseOptions {
+      strict: true,
+      ..ParseOptions::default()
+    },
+  )
+}
+
+// 对外接口：同时返回注释（以其后紧随的值的JSON Pointer为键）
+pub fn parse_with_comments(text: &str) -> ParseResult<(Value, HashMap<String, String>)> {
+  let mut parser = Parser::new(text).with_comments();
+  let result = parser.value()?;
+  parser.white()?;
+  if parser.ch.is_some() {
+    return Err(parser.error("Syntax error".to_string()));
+  }
+  Ok((result, parser.comments))
+}
+
+// 对外接口：序列化为紧凑的JSON5
+pub fn to_string(value: &Value) -> String {
+  let mut output = String::new();
+  write_value(&mut output, value, None, 0);
+  output
+}
+
+// 对外接口：序列化为带缩进的JSON5，每个元素占一行
+pub fn to_string_pretty(value: &Value, indent: usize) -> String {
+  let mut output = String::new();
+  write_value(&mut output, value, Some(indent), 0);
+  output
+}
+
+fn write_value(output: &mut String, value: &Value, indent: Option<usize>, depth: usize) {
+  match value {
+    Value::Null => output.push_str("null"),
+    Value::Bool(b) => output.push_str(if *b { "true" } else { "false" }),
+    Value::Number(n) => match n.as_f64() {
+      // JSON5 允许非有限数，直接输出为单词
+      Some(f) if f.is_nan() => output.push_str("NaN"),
+      Some(f) if f.is_infinite() => output.push_str(if f > 0.0 { "Infinity" } else { "-Infinity" }),
+      _ => output.push_str(&n.to_string()),
+    },
+    Value::String(s) => write_string(output, s),
+    Value::Array(arr) => {
+      if arr.is_empty() {
+        output.push_str("[]");
+        return;
+      }
+      output.push('[');
+      for (i, v) in arr.iter().enumerate() {
+        if i > 0 {
+          output.push(',');
+        }
+        write_newline(output, indent, depth + 1);
+        write_value(output, v, indent, depth + 1);
+      }
+      write_newline(output, indent, depth);
+      output.push(']');
+    }
+    Value::Object(obj) => {
+      if obj.is_empty() {
+        output.push_str("{}");
+        return;
+      }
+      output.push('{');
+      for (i, (k, v)) in obj.iter().enumerate() {
+        if i > 0 {
+          output.push(',');
+        }
+        write_newline(output, indent, depth + 1);
+        if is_identifier(k) {
+          output.push_str(k);
+        } else {
+          write_string(output, k);
+        }
+        output.push(':');
+        if indent.is_some() {
+          output.push(' ');
+        }
+        write_value(output, v, indent, depth + 1);
+      }
+      write_newline(output, indent, depth);
+      output.push('}');
+    }
+  }
+}
+
+// 换行并缩进（仅 pretty 模式）
+fn write_newline(output: &mut String, indent: Option<usize>, depth: usize) {
+  if let Some(indent) = indent {
+    output.push('\n');
+    output.push_str(&" ".repeat(indent * depth));
+  }
+}
+
+// 输出字符串，优先使用无需转义的引号
+fn write_string(output: &mut String, s: &str) {
+  let quote = if s.contains('"') && !s.contains('\'') {
+    '\''
+  } else {
+    '"'
+  };
+  output.push(quote);
+  for c in s.chars() {
+    match c {
+      '\\' => output.push_str("\\\\"),
+      '\u{0008}' => output.push_str("\\b"),
+      '\u{000C}' => output.push_str("\\f"),
+      '\n' => output.push_str("\\n"),
+      '\r' => output.push_str("\\r"),
+      '\t' => output.push_str("\\t"),
+      c if c == quote => {
+        output.push('\\');
+        output.push(c);
+      }
+      c if c < ' ' || c == '\u{2028}' || c == '\u{2029}' => {
+        output.push_str(&format!("\\u{:04X}", c as u32));
+      }
+      c => output.push(c),
+    }
+  }
+  output.push(quote);
+}
+
+// 检查key能否不加引号输出
+fn is_identifier(key: &str) -> bool {
+  let mut chars = key.chars();
+  match chars.next() {
+    Some(c) if c == '$' || c == '_' || c.is_alphabetic() => {
+      chars.all(|c| c == '$' || c == '_' || c.is_alphanumeric())
+    }
+    _ => false,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use super::*;
+
+  #[test]
+  fn unquoted_identifier_keys() {
+    let value = parse("{foo: 1, $bar_2: 2, _baz: 3}").unwrap();
+    assert_eq!(value, json!({"foo": 1, "$bar_2": 2, "_baz": 3}));
+    assert!(parse("{2a: 1}").is_err());
+  }
+
+  #[test]
+  fn surrogate_pairs_are_combined() {
+    assert_eq!(parse(r#""\uD83D\uDE00""#).unwrap(), json!("😀"));
+    assert_eq!(parse(r#""\ud83d\ude00 ok""#).unwrap(), json!("😀 ok"));
+    assert!(parse(r#""\uD83D""#).is_err());
+    assert!(parse(r#""\uD83Dx""#).is_err());
+  }
+
+  #[test]
+  fn leading_and_trailing_dot_numbers() {
+    assert_eq!(parse(".5").unwrap(), json!(0.5));
+    assert_eq!(parse("-.5").unwrap(), json!(-0.5));
+    assert_eq!(parse("5.").unwrap().as_f64(), Some(5.0));
+    assert!(parse(".").is_err());
+    assert!(parse_strict(".5").is_err());
+  }
+
+  #[test]
+  fn duplicate_key_policies() {
+    let text = r#"{"a": 1, "b": 2, "a": 3}"#;
+    let with_policy = |duplicate_keys| {
+      parse_with_options(
+        text,
+        ParseOptions {
+          duplicate_keys,
+          ..ParseOptions::default()
+        },
+      )
+    };
+    assert_eq!(
+      with_policy(DuplicateKeyPolicy::LastWins).unwrap(),
+      json!({"a": 3, "b": 2})
+    );
+    assert_eq!(
+      with_policy(DuplicateKeyPolicy::FirstWins).unwrap(),
+      json!({"a": 1, "b": 2})
+    );
+    let error = with_policy(DuplicateKeyPolicy::Error).unwrap_err();
+    assert!(error.message.starts_with(r#"Duplicate key "a""#));
+    assert_eq!((error.line, error.column), (1, 18));
+  }
+
+  #[test]
+  fn nesting_depth_is_limited() {
+    let options = ParseOptions {
+      max_depth: 3,
+      ..ParseOptions::default()
+    };
+    assert!(parse_with_options("[[[1]]]", options.clone()).is_ok());
+    let error = parse_with_options("[[[[1]]]]", options).unwrap_err();
+    assert!(error.message.starts_with("Maximum nesting depth exceeded"));
+    // 默认的上限足以防止栈溢出
+    let deep = "[".repeat(100_000);
+    assert!(parse(&deep).is_err());
+  }
+
+  #[test]
+  fn large_integers_keep_precision() {
+    assert_eq!(
+      parse("9007199254740993").unwrap(),
+      json!(9007199254740993u64)
+    );
+    assert_eq!(parse("-9223372036854775808").unwrap(), json!(i64::MIN));
+    assert_eq!(parse("18446744073709551615").unwrap(), json!(u64::MAX));
+    // 超出 u64 范围时回退到 f64
+    assert_eq!(
+      parse("18446744073709551616").unwrap(),
+      json!(1.8446744073709552e19)
+    );
+  }
+
+  #[test]
+  fn strict_mode_rejects_json5_extensions() {
+    assert_eq!(
+      parse_strict(r#"{"a": [1, 2.5, "x", true, null]}"#).unwrap(),
+      json!({"a": [1, 2.5, "x", true, null]})
+    );
+    for text in [
+      "// comment\n1",
+      "{a: 1}",
+      "'single'",
+      "0x10",
+      "+1",
+      "Infinity",
+    ] {
+      assert!(parse_strict(text).is_err(), "{} should be rejected", text);
+      assert!(parse(text).is_ok(), "{} should be accepted as JSON5", text);
+    }
+  }
+
+  #[test]
+  fn error_span_runs_up_to_the_current_character() {
+    let error = parse("[1, 2 3]").unwrap_err();
+    assert_eq!((error.line, error.column), (1, 7));
+    assert!(error.span.is_some());
+    assert_eq!(parse("[1,").unwrap_err().span, None);
+  }
+
+  #[test]
+  fn signed_hex_numbers() {
+    assert_eq!(parse("0x1F").unwrap(), json!(31));
+    assert_eq!(parse("-0x1F").unwrap(), json!(-31));
+    assert_eq!(parse("+0XfF").unwrap(), json!(255));
+    assert!(parse("0x").is_err());
+  }
+
+  #[test]
+  fn nul_and_end_of_input_are_reported_differently() {
+    let nul = parse("[1\0]").unwrap_err();
+    assert!(nul.message.contains("'\\0'"), "{}", nul.message);
+    let eof = parse("[1").unwrap_err();
+    assert!(eof.message.contains("EOF"), "{}", eof.message);
+  }
+
+  #[test]
+  fn hex_floats_are_rejected() {
+    for text in ["0x1.8p3", "0x1p3", "0x1P-2"] {
+      let error = parse(text).unwrap_err();
+      assert!(
+        error
+          .message
+          .starts_with("Hexadecimal floating-point literals are not allowed in JSON5")
+      );
+    }
+  }
+
+  #[test]
+  fn comments_attach_to_the_following_member() {
+    let text = "{\n  // Title\n  \"title\": \"x\",\n  /* Items */\n  \"items\": [\n    // First\n    \"a\"\n  ]\n}";
+    let (_, comments) = parse_with_comments(text).unwrap();
+    assert_eq!(comments.get("/title").map(String::as_str), Some("Title"));
+    assert_eq!(comments.get("/items").map(String::as_str), Some("Items"));
+    assert_eq!(comments.get("/items/0").map(String::as_str), Some("First"));
+  }
+
+  #[test]
+  fn detached_and_trailing_comments_are_dropped() {
+    let (_, comments) =
+      parse_with_comments("{\"a\": \"x\", \"b\": \"y\", // trailing\n  \"c\": \"z\"}").unwrap();
+    assert!(comments.is_empty(), "{:?}", comments);
+    let (_, comments) = parse_with_comments("{\n  // detached\n\n  \"a\": 1\n}").unwrap();
+    assert!(comments.is_empty(), "{:?}", comments);
+  }
+
+  #[test]
+  fn comment_pointers_are_escaped() {
+    let (_, comments) = parse_with_comments("{\n  // Note\n  \"a/b~c\": 1\n}").unwrap();
+    assert_eq!(comments.get("/a~1b~0c").map(String::as_str), Some("Note"));
+  }
+
+  #[test]
+  fn objects_keep_source_key_order() {
+    let json_value = parse(r#"{"b": 1, "a": 2, "c": 3}"#).unwrap();
+    let keys: Vec<&str> = json_value
+      .as_object()
+      .unwrap()
+      .keys()
+      .map(String::as_str)
+      .collect();
+    assert_eq!(keys, ["b", "a", "c"]);
+  }
+}
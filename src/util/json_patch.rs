@@ -1,7 +1,16 @@
-use regex::RegexSet;
+use std::{
+  collections::{HashMap, HashSet},
+  path::Path,
+};
+
+use anyhow::{Result, bail};
+use clap::ValueEnum;
 use serde_json::{Value, json};
 
-use crate::util::patterns::PatternConfig;
+use crate::util::{
+  log::log_warn,
+  patterns::{DEFAULT_PREFIX, Marker, PatternConfig, PatternSet},
+};
 
 /// Starbound支持的JSON Patch类型：
 /// 分别对应标准的Vec<Value> 或 Starbound特别支持的Vec<Vec<Value>>
@@ -12,54 +21,437 @@ pub enum PatchData {
 }
 
 impl PatchData {
+  /// 从解析后的 patch 文件内容构造，既不是操作数组也不是批次数组时返回 None
+  pub fn from_value(value: Value) -> Option<Self> {
+    let Value::Array(entries) = value else {
+      return None;
+    };
+    if !entries.is_empty() && entries.iter().all(Value::is_array) {
+      let batches = entries
+        .into_iter()
+        .map(|batch| match batch {
+          Value::Array(patch_operations) => patch_operations,
+          _ => unreachable!(),
+        })
+        .collect();
+      Some(PatchData::BatchesPatch(batches))
+    } else if entries.iter().all(Value::is_object) {
+      Some(PatchData::CommonPatch(entries))
+    } else {
+      None
+    }
+  }
+
+  /// 追加另一个 patch 的条目，两者形式不同时整体转换为 BatchesPatch
+  pub fn extend(&mut self, other: PatchData) {
+    match (&mut *self, other) {
+      (PatchData::CommonPatch(patch_operations), PatchData::CommonPatch(other)) => {
+        patch_operations.extend(other)
+      }
+      (PatchData::BatchesPatch(patch_operations), PatchData::BatchesPatch(other)) => {
+        patch_operations.extend(other)
+      }
+      (PatchData::BatchesPatch(patch_operations), PatchData::CommonPatch(other)) => {
+        patch_operations.push(other)
+      }
+      (PatchData::CommonPatch(patch_operations), PatchData::BatchesPatch(other)) => {
+        let mut batches = vec![std::mem::take(patch_operations)];
+        batches.extend(other);
+        *self = PatchData::BatchesPatch(batches);
+      }
+    }
+  }
+
   pub fn is_empty(&self) -> bool {
     match self {
       PatchData::CommonPatch(patch_operations) => patch_operations.is_empty(),
       PatchData::BatchesPatch(patch_operations) => patch_operations.iter().all(|x| x.is_empty()),
     }
   }
+
+  /// 条目数量（CommonPatch为操作数，BatchesPatch为批次数）
+  pub fn entry_count(&self) -> usize {
+    match self {
+      PatchData::CommonPatch(patch_operations) => patch_operations.len(),
+      PatchData::BatchesPatch(patch_operations) => patch_operations.len(),
+    }
+  }
+
+  /// 除 test operation 外的操作总数，即生成的 replace/add 操作数
+  pub fn operation_count(&self) -> usize {
+    self
+      .operations()
+      .iter()
+      .filter(|patch_operation| patch_operation["op"] != "test")
+      .count()
+  }
+
+  /// 批次数（CommonPatch为0）
+  pub fn batch_count(&self) -> usize {
+    match self {
+      PatchData::CommonPatch(_) => 0,
+      PatchData::BatchesPatch(patch_operations) => patch_operations.len(),
+    }
+  }
+
+  /// 所有patch操作（BatchesPatch会被展开）
+  pub fn operations(&self) -> Vec<&Value> {
+    match self {
+      PatchData::CommonPatch(patch_operations) => patch_operations.iter().collect(),
+      PatchData::BatchesPatch(patch_operations) => patch_operations.iter().flatten().collect(),
+    }
+  }
+
+  /// 只保留满足条件的条目（BatchesPatch中需整批满足）
+  pub fn retain(&mut self, mut f: impl FnMut(&Value) -> bool) {
+    match self {
+      PatchData::CommonPatch(patch_operations) => patch_operations.retain(|x| f(x)),
+      PatchData::BatchesPatch(patch_operations) => {
+        patch_operations.retain(|batch| batch.iter().all(&mut f))
+      }
+    }
+  }
+
+  /// 去掉完全相同的条目，保留首次出现的顺序（BatchesPatch中按整批比较），返回去掉的条目数
+  pub fn dedup(&mut self) -> usize {
+    let before = self.entry_count();
+    let mut seen = HashSet::new();
+    match self {
+      PatchData::CommonPatch(patch_operations) => {
+        patch_operations.retain(|x| seen.insert(x.to_string()))
+      }
+      PatchData::BatchesPatch(patch_operations) => {
+        patch_operations.retain(|batch| seen.insert(Value::from(batch.clone()).to_string()))
+      }
+    }
+    before - self.entry_count()
+  }
+
+  /// 按 path 字符串稳定排序（BatchesPatch按每批第一个操作的 path 排序，批内顺序不变）
+  pub fn sort_by_path(&mut self) {
+    fn path_of(patch_operation: Option<&Value>) -> &str {
+      patch_operation
+        .and_then(|x| x["path"].as_str())
+        .unwrap_or_default()
+    }
+    match self {
+      PatchData::CommonPatch(patch_operations) => {
+        patch_operations.sort_by(|a, b| path_of(Some(a)).cmp(path_of(Some(b))))
+      }
+      PatchData::BatchesPatch(patch_operations) => {
+        patch_operations.sort_by(|a, b| path_of(a.first()).cmp(path_of(b.first())))
+      }
+    }
+  }
+
+  /// 按基础文件确定 add/replace 操作的类型：路径在基础文件中存在时为 replace，否则为 add
+  /// test operation 保持不变，返回改为 add 的操作数量
+  pub fn set_op_kinds_from_base(&mut self, base: &Value) -> usize {
+    let mut add_count = 0;
+    let set_op_kind = |patch_operation: &mut Value| {
+      if !matches!(patch_operation["op"].as_str(), Some("add" | "replace")) {
+        return;
+      }
+      let json_pointer = patch_operation["path"].as_str().unwrap_or_default();
+      let op_kind = if base.pointer(json_pointer).is_some() {
+        OpKind::Replace
+      } else {
+        add_count += 1;
+        OpKind::Add
+      };
+      patch_operation["op"] = Value::String(op_kind.as_str().to_string());
+    };
+    match self {
+      PatchData::CommonPatch(patch_operations) => patch_operations.iter_mut().for_each(set_op_kind),
+      PatchData::BatchesPatch(patch_operations) => {
+        patch_operations.iter_mut().flatten().for_each(set_op_kind)
+      }
+    }
+    add_count
+  }
+
+  /// 在所有操作的 path（以及 from）前加上指针前缀
+  pub fn prefix_paths(&mut self, pointer_prefix: &str) {
+    let prefix_operation = |patch_operation: &mut Value| {
+      for key in ["path", "from"] {
+        if let Some(json_pointer) = patch_operation[key].as_str() {
+          patch_operation[key] = Value::String(format!("{}{}", pointer_prefix, json_pointer));
+        }
+      }
+    };
+    match self {
+      PatchData::CommonPatch(patch_operations) => {
+        patch_operations.iter_mut().for_each(prefix_operation)
+      }
+      PatchData::BatchesPatch(patch_operations) => patch_operations
+        .iter_mut()
+        .flatten()
+        .for_each(prefix_operation),
+    }
+  }
+
+  /// 递归按字母顺序排序所有对象的键，数组保持原有顺序
+  pub fn sort_keys(&mut self) {
+    match self {
+      PatchData::CommonPatch(patch_operations) => {
+        patch_operations.iter_mut().for_each(sort_value_keys)
+      }
+      PatchData::BatchesPatch(patch_operations) => patch_operations
+        .iter_mut()
+        .flatten()
+        .for_each(sort_value_keys),
+    }
+  }
+
+  /// 截断到指定条目数量
+  pub fn truncate(&mut self, len: usize) {
+    match self {
+      PatchData::CommonPatch(patch_operations) => patch_operations.truncate(len),
+      PatchData::BatchesPatch(patch_operations) => patch_operations.truncate(len),
+    }
+  }
+}
+
+/// 递归按字母顺序排序 Value 中所有对象的键，数组保持原有顺序
+pub fn sort_value_keys(json_value: &mut Value) {
+  match json_value {
+    Value::Object(object_value) => {
+      object_value.sort_keys();
+      object_value.values_mut().for_each(sort_value_keys);
+    }
+    Value::Array(array_value) => array_value.iter_mut().for_each(sort_value_keys),
+    _ => {}
+  }
+}
+
+/// 生成的 patch 操作类型
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OpKind {
+  /// 替换已存在的值
+  #[default]
+  Replace,
+  /// 添加值（目标不存在时也可生效）
+  Add,
+}
+
+impl OpKind {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      OpKind::Replace => "replace",
+      OpKind::Add => "add",
+    }
+  }
+}
+
+/// 生成的字符串值中换行符的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum NewlineMode {
+  /// 保持原样
+  #[default]
+  Keep,
+  /// 统一为 "\n"
+  Lf,
+  /// 统一为 "\r\n"
+  Crlf,
+}
+
+impl NewlineMode {
+  /// 按处理方式转换字符串中的换行符，单独的 "\r" 也视为换行
+  pub fn apply(&self, string_value: &str) -> String {
+    match self {
+      NewlineMode::Keep => string_value.to_string(),
+      NewlineMode::Lf => string_value.replace("\r\n", "\n").replace('\r', "\n"),
+      NewlineMode::Crlf => NewlineMode::Lf.apply(string_value).replace('\n', "\r\n"),
+    }
+  }
+}
+
+/// 生成 patch 时的选项
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PatchOptions {
+  /// 空字符串和纯空白字符串也生成操作
+  pub include_blank: bool,
+  /// 为每个操作生成 test operation
+  pub gen_test_operation: bool,
+  /// test operation 同时检查原始值
+  pub test_value: bool,
+  /// 生成的操作类型
+  pub op_kind: OpKind,
+  /// 生成的字符串值中换行符的处理方式
+  pub newlines: NewlineMode,
+}
+
+/// 按 RFC 6901 转义 JSON Pointer 中的单个引用片段（'~' -> "~0"，'/' -> "~1"）
+pub fn escape_pointer_token(token: &str) -> String {
+  token.replace('~', "~0").replace('/', "~1")
+}
+
+/// 还原经过转义的 JSON Pointer 引用片段
+pub fn unescape_pointer_token(token: &str) -> String {
+  token.replace("~1", "/").replace("~0", "~")
+}
+
+/// 把以 '/' 分隔的相对路径转换为 JSON Pointer，每段按 RFC 6901 转义，如 "items/a~b.object" -> "/items/a~0b.object"
+pub fn path_to_pointer(relative_path: &str) -> String {
+  relative_path
+    .split('/')
+    .filter(|segment| !segment.is_empty())
+    .map(|segment| format!("/{}", escape_pointer_token(segment)))
+    .collect()
+}
+
+/// 把 JSON Pointer 转换为便于阅读的形式，仅用于显示，如 "/a/b~1c/0" -> "a.b/c[0]"
+/// 纯数字的片段显示为下标，含 '.'、'[' 、']' 或为空的键显示为 ["键"]
+pub fn pointer_to_human(json_pointer: &str) -> String {
+  format_human_pointer(json_pointer, None)
+}
+
+/// 同 pointer_to_human，但按源 JSON 区分纯数字的对象键和数组下标
+/// 如对象键 "0" 显示为 ["0"]，数组下标 0 显示为 [0]，如 "/a/0/b" -> "a[\"0\"].b"
+pub fn pointer_to_human_in(json_value: &Value, json_pointer: &str) -> String {
+  format_human_pointer(json_pointer, Some(json_value))
+}
+
+/// 转换为便于阅读的形式，json_value 为 None 时纯数字的片段一律视为数组下标
+fn format_human_pointer(json_pointer: &str, json_value: Option<&Value>) -> String {
+  let mut human = String::new();
+  let mut current = json_value;
+  for token in json_pointer.split('/').skip(1) {
+    let key = unescape_pointer_token(token);
+    let in_object = matches!(current, Some(Value::Object(_)));
+    current = match current {
+      Some(Value::Object(object_value)) => object_value.get(&key),
+      Some(Value::Array(array_value)) => key.parse::<usize>().ok().and_then(|i| array_value.get(i)),
+      _ => None,
+    };
+    let is_numeric = !key.is_empty() && key.bytes().all(|b| b.is_ascii_digit());
+    if is_numeric && !in_object {
+      human.push_str(&format!("[{}]", key));
+    } else if is_numeric || key.is_empty() || key.contains(['.', '[', ']']) {
+      human.push_str(&format!("[{}]", serde_json::to_string(&key).unwrap()));
+    } else {
+      if !human.is_empty() {
+        human.push('.');
+      }
+      human.push_str(&key);
+    }
+  }
+  human
+}
+
+/// 源文件中的注释，以其后紧随的值的JSON Pointer为键
+pub type Comments = HashMap<String, String>;
+
+/// 如果该路径前有注释，则作为翻译上下文附加到 patch 操作中
+fn attach_comment(patch_operation: &mut Value, comments: Option<&Comments>, json_pointer: &str) {
+  if let Some(comment) = comments.and_then(|c| c.get(json_pointer)) {
+    patch_operation["comment"] = Value::String(comment.clone());
+  }
+}
+
+/// 按选项转换换行符后为字符串添加翻译标记
+fn mark_string(string_value: &str, marker: &Marker, options: &PatchOptions) -> String {
+  marker.mark(&options.newlines.apply(string_value))
+}
+
+/// 检查字符串是否需要添加前缀
+/// 已带有前缀的字符串不再重复添加；空字符串和纯空白字符串默认跳过
+fn should_mark(string_value: &str, marker: &Marker, include_blank: bool) -> bool {
+  if marker.is_marked(string_value) {
+    return false;
+  }
+  include_blank || !string_value.trim().is_empty()
+}
+
+/// 检查字符串是否与其键相同：键为 JSON Pointer 中最后一个不是数组下标的片段，
+/// 因此数组元素与数组本身的键比较（纯数字的片段都视为数组下标）
+fn equals_key(json_pointer: &str, string_value: &str) -> bool {
+  json_pointer.strip_prefix('/').is_some_and(|tokens| {
+    tokens
+      .rsplit('/')
+      .find(|token| !is_array_index(token))
+      .is_some_and(|key| unescape_pointer_token(key) == string_value)
+  })
+}
+
+/// 检查 JSON Pointer 片段是否为数组下标
+fn is_array_index(token: &str) -> bool {
+  !token.is_empty() && token.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// 检查匹配路径下的字符串是否需要翻译，开启 skip_if_equals_key 时跳过与其键相同的字符串
+fn should_mark_at(
+  string_value: &str,
+  json_pointer: &str,
+  pattern_set: &PatternSet,
+  marker: &Marker,
+  options: &PatchOptions,
+) -> bool {
+  should_mark(string_value, marker, options.include_blank)
+    && !(pattern_set.skip_if_equals_key() && equals_key(json_pointer, string_value))
+}
+
+/// 为匹配路径的数组中需要翻译的字符串元素加上翻译标记，其余元素保持不变
+fn mark_array(
+  array_value: &[Value],
+  json_pointer: &str,
+  pattern_set: &PatternSet,
+  marker: &Marker,
+  options: &PatchOptions,
+) -> Vec<Value> {
+  array_value
+    .iter()
+    .map(|x| match x {
+      Value::String(string_value)
+        if should_mark_at(string_value, json_pointer, pattern_set, marker, options) =>
+      {
+        Value::String(mark_string(string_value, marker, options))
+      }
+      _ => x.clone(),
+    })
+    .collect()
 }
 
 /// 递归遍历 JSON，生成 patch 操作数组
 fn gen_patch_from_json(
   json_value: &Value,
   json_pointer: String,
-  regex_set: Option<&RegexSet>,
-  patch_operations: &mut Vec<Value>,
+  pattern_set: &PatternSet,
+  comments: Option<&Comments>,
+  marker: &Marker,
+  options: &PatchOptions,
+  patch_operations: &mut Vec<(Value, Value)>,
 ) {
   match json_value {
-    Value::String(string_value) => {
-      if let Some(set) = regex_set {
-        if set.is_match(&json_pointer) {
-          // 生成 patch 操作
-          patch_operations.push(json!({
-            "op": "replace",
-            "path": json_pointer,
-            "value": format!("(T) {}", string_value)
-          }));
-        }
-      }
+    Value::String(string_value)
+      if pattern_set.is_match(&json_pointer)
+        && should_mark_at(string_value, &json_pointer, pattern_set, marker, options) =>
+    {
+      // 生成 patch 操作
+      let mut patch_operation = json!({
+        "op": options.op_kind.as_str(),
+        "path": json_pointer,
+        "value": mark_string(string_value, marker, options)
+      });
+      attach_comment(&mut patch_operation, comments, &json_pointer);
+      patch_operations.push((patch_operation, json_value.clone()));
     }
     Value::Array(array_value) => {
-      if let Some(set) = regex_set {
-        if set.is_match(&json_pointer) {
-          // 生成 patch
-          let new_array: Vec<Value> = array_value
-            .iter()
-            .map(|x| match x {
-              Value::String(string_value) => Value::String(format!("(T) {}", string_value)),
-              // unreachale???
-              _ => x.clone(),
-            })
-            .collect();
-          patch_operations.push(json!({
-            "op": "replace",
+      if pattern_set.is_match(&json_pointer) {
+        // 生成 patch
+        let new_array = mark_array(array_value, &json_pointer, pattern_set, marker, options);
+        // 所有元素都已带有前缀时无需生成
+        if new_array != *array_value {
+          let mut patch_operation = json!({
+            "op": options.op_kind.as_str(),
             "path": json_pointer,
             "value": new_array
-          }));
-          // 不再递归数组内部
-          return;
+          });
+          attach_comment(&mut patch_operation, comments, &json_pointer);
+          patch_operations.push((patch_operation, json_value.clone()));
         }
+        // 不再递归数组内部
+        return;
       }
       // 递归数组元素
       for (index, value) in array_value.iter().enumerate() {
@@ -68,162 +460,885 @@ fn gen_patch_from_json(
         } else {
           format!("{}/{}", json_pointer, index)
         };
-        gen_patch_from_json(value, next_pointer, regex_set, patch_operations);
+        gen_patch_from_json(
+          value,
+          next_pointer,
+          pattern_set,
+          comments,
+          marker,
+          options,
+          patch_operations,
+        );
       }
     }
     Value::Object(object_value) => {
       for (key, value) in object_value {
         let next_pointer = if json_pointer.is_empty() {
-          format!("/{}", key)
+          format!("/{}", escape_pointer_token(key))
         } else {
-          format!("{}/{}", json_pointer, key)
+          format!("{}/{}", json_pointer, escape_pointer_token(key))
         };
 
-        gen_patch_from_json(value, next_pointer, regex_set, patch_operations);
+        gen_patch_from_json(
+          value,
+          next_pointer,
+          pattern_set,
+          comments,
+          marker,
+          options,
+          patch_operations,
+        );
       }
     }
     _ => {}
   }
 }
 
+/// 处理 patch 文件时收集的结果
+#[derive(Default)]
+struct PatchCollector {
+  /// 生成的操作及对应的原始值
+  operations: Vec<(Value, Value)>,
+  /// 因追加到数组末尾（"/-"）而跳过、但含有需要翻译的值的 add 操作路径
+  skipped_appends: Vec<String>,
+}
+
 /// 递归处理 JSON 数据，生成 patch 操作
 /// is_patch_value: 是否处理 patch 对象中的 value 部分
 fn gen_patch_from_json_patch(
   json_value: &Value,
   operation_path: &str,
-  regex_set: Option<&RegexSet>,
-  patch_operations: &mut Vec<Value>,
+  pattern_set: &PatternSet,
+  marker: &Marker,
+  options: &PatchOptions,
+  collector: &mut PatchCollector,
   is_patch_value: bool,
 ) {
   match json_value {
-    Value::String(string_value) => {
-      if let Some(set) = regex_set {
-        if set.is_match(operation_path) {
-          patch_operations.push(json!({
-            "op": "replace",
-            "path": operation_path,
-            "value": format!("(T) {}", string_value)
-          }));
-        }
-      }
+    // 只有 add/replace 的 value 中的值有对应的路径
+    Value::String(string_value)
+      if is_patch_value
+        && pattern_set.is_match(operation_path)
+        && should_mark_at(string_value, operation_path, pattern_set, marker, options) =>
+    {
+      collector.operations.push((
+        json!({
+          "op": options.op_kind.as_str(),
+          "path": operation_path,
+          "value": mark_string(string_value, marker, options)
+        }),
+        json_value.clone(),
+      ));
     }
     Value::Array(array_value) => {
-      if let Some(set) = regex_set {
-        if set.is_match(operation_path) {
-          let new_array: Vec<Value> = array_value
-            .iter()
-            .map(|x| match x {
-              Value::String(string_value) => Value::String(format!("(T) {}", string_value)),
-              _ => x.clone(),
-            })
-            .collect();
-          patch_operations.push(json!({
-            "op": "replace",
-            "path": operation_path,
-            "value": new_array
-          }));
-          return;
+      if is_patch_value && pattern_set.is_match(operation_path) {
+        let new_array = mark_array(array_value, operation_path, pattern_set, marker, options);
+        if new_array != *array_value {
+          collector.operations.push((
+            json!({
+              "op": options.op_kind.as_str(),
+              "path": operation_path,
+              "value": new_array
+            }),
+            json_value.clone(),
+          ));
         }
+        return;
       }
       for (i, v) in array_value.iter().enumerate() {
         let next_path = format!("{}/{}", operation_path, i);
-        gen_patch_from_json_patch(v, &next_path, regex_set, patch_operations, is_patch_value);
+        gen_patch_from_json_patch(
+          v,
+          &next_path,
+          pattern_set,
+          marker,
+          options,
+          collector,
+          is_patch_value,
+        );
       }
     }
     Value::Object(object_value) => {
-      if !is_patch_value {
-        // 处理 patch 对象
-        if let (Some(Value::String(op)), Some(Value::String(path)), Some(val)) = (
-          object_value.get("op"),
-          object_value.get("path"),
-          object_value.get("value"),
-        ) {
-          if op == "replace" || op == "add" {
-            gen_patch_from_json_patch(val, path, regex_set, patch_operations, true);
+      // 处理 patch 对象（value 中嵌套的完整 patch 对象也按 patch 处理）
+      if (!is_patch_value || is_patch_operation(object_value))
+        && let Some(Value::String(op)) = object_value.get("op")
+      {
+        match op.as_str() {
+          // 只有 add/replace 的 value 需要翻译
+          "add" | "replace" => {
+            let (Some(Value::String(path)), Some(val)) =
+              (object_value.get("path"), object_value.get("value"))
+            else {
+              return;
+            };
+            // 追加到数组末尾（"/-"）的元素在应用后的下标未知，无法生成指向其中字段的路径
+            // 跳过，但其中有需要翻译的值时记录下来以便提示
+            if op == "add" && (path == "-" || path.ends_with("/-")) {
+              let mut appended = PatchCollector::default();
+              gen_patch_from_json_patch(
+                val,
+                path,
+                pattern_set,
+                marker,
+                options,
+                &mut appended,
+                true,
+              );
+              if !appended.operations.is_empty() {
+                collector.skipped_appends.push(path.clone());
+              }
+              return;
+            }
+            gen_patch_from_json_patch(val, path, pattern_set, marker, options, collector, true);
             return;
           }
+          // 没有需要翻译的值
+          "remove" | "copy" | "move" | "test" => return,
+          _ => {}
         }
       }
 
-      // 递归处理对象字段
+      // 不在 value 中的其他对象没有对应的路径
+      if !is_patch_value {
+        return;
+      }
+      // 递归处理对象字段，键按 RFC 6901 转义
       for (k, v) in object_value {
-        let next_path = if is_patch_value {
-          format!("{}/{}", operation_path, k)
-        } else {
-          k.to_string()
-        };
-        gen_patch_from_json_patch(v, &next_path, regex_set, patch_operations, is_patch_value);
+        let next_path = format!("{}/{}", operation_path, escape_pointer_token(k));
+        gen_patch_from_json_patch(
+          v,
+          &next_path,
+          pattern_set,
+          marker,
+          options,
+          collector,
+          is_patch_value,
+        );
       }
     }
     _ => {}
   }
 }
 
+/// 检查生成的 patch 是否格式正确，用于发现生成逻辑的错误：
+/// 每个操作的 op 为已知操作，path（及 move/copy 的 from）为合法的 JSON Pointer，
+/// add/replace 带有 value，BatchesPatch 中每个批次都不为空
+pub fn validate_patch(patch_data: &PatchData) -> Result<()> {
+  if let PatchData::BatchesPatch(batches) = patch_data
+    && let Some(index) = batches.iter().position(Vec::is_empty)
+  {
+    bail!("Batch {} is empty", index);
+  }
+  for (index, patch_operation) in patch_data.operations().into_iter().enumerate() {
+    let Some(object_value) = patch_operation.as_object() else {
+      bail!("Operation {} is not an object", index);
+    };
+    let op = match object_value.get("op").and_then(Value::as_str) {
+      Some(op @ ("add" | "replace" | "remove" | "copy" | "move" | "test")) => op,
+      Some(op) => bail!("Operation {} has an unknown op \"{}\"", index, op),
+      None => bail!("Operation {} has no op", index),
+    };
+    let mut pointer_fields = vec!["path"];
+    if matches!(op, "copy" | "move") {
+      pointer_fields.push("from");
+    }
+    for field in pointer_fields {
+      match object_value.get(field).and_then(Value::as_str) {
+        Some(pointer) if is_valid_pointer(pointer) => {}
+        Some(pointer) => bail!(
+          "Operation {} ({}) has an invalid {} \"{}\"",
+          index,
+          op,
+          field,
+          pointer
+        ),
+        None => bail!("Operation {} ({}) has no {}", index, op, field),
+      }
+    }
+    if matches!(op, "add" | "replace") && !object_value.contains_key("value") {
+      bail!("Operation {} ({}) has no value", index, op);
+    }
+  }
+  Ok(())
+}
+
+/// 检查是否为语法正确的 JSON Pointer（RFC 6901）：为空或以 '/' 开头，'~' 后只能是 '0' 或 '1'
+fn is_valid_pointer(json_pointer: &str) -> bool {
+  (json_pointer.is_empty() || json_pointer.starts_with('/'))
+    && json_pointer
+      .split('~')
+      .skip(1)
+      .all(|rest| rest.starts_with(['0', '1']))
+}
+
+/// 检查对象是否为格式正确的 patch 操作：op 为已知操作且 path 为字符串
+fn is_patch_operation(object_value: &serde_json::Map<String, Value>) -> bool {
+  matches!(
+    object_value.get("op").and_then(Value::as_str),
+    Some("add" | "replace" | "remove" | "copy" | "move" | "test")
+  ) && object_value.get("path").is_some_and(Value::is_string)
+}
+
 /// 处理JSON数据，生成从JSON本身的patch操作数组
 fn process_json(
   json_value: &Value,
-  regex_set: Option<&RegexSet>,
-  gen_test_operation: bool,
+  pattern_set: &PatternSet,
+  comments: Option<&Comments>,
+  marker: &Marker,
+  options: &PatchOptions,
 ) -> PatchData {
   let mut patch_operations = Vec::new();
-  gen_patch_from_json(json_value, String::new(), regex_set, &mut patch_operations);
+  gen_patch_from_json(
+    json_value,
+    String::new(),
+    pattern_set,
+    comments,
+    marker,
+    options,
+    &mut patch_operations,
+  );
 
-  if gen_test_operation {
-    generate_test_operation(&patch_operations)
+  if options.gen_test_operation {
+    generate_test_operation(patch_operations, options.test_value)
   } else {
-    PatchData::CommonPatch(patch_operations)
+    PatchData::CommonPatch(patch_operations.into_iter().map(|(x, _)| x).collect())
   }
 }
 
 /// 处理JSON数据，生成一维patch操作数组
+/// 同时返回因追加到数组末尾而跳过的 add 操作路径
 fn process_json_patch(
   json_value: &Value,
-  regex_set: Option<&RegexSet>,
-  gen_test_operation: bool,
-) -> PatchData {
-  let mut patch_operations = Vec::new();
-  gen_patch_from_json_patch(json_value, "", regex_set, &mut patch_operations, false);
+  pattern_set: &PatternSet,
+  marker: &Marker,
+  options: &PatchOptions,
+) -> (PatchData, Vec<String>) {
+  let mut collector = PatchCollector::default();
+  gen_patch_from_json_patch(
+    json_value,
+    "",
+    pattern_set,
+    marker,
+    options,
+    &mut collector,
+    false,
+  );
 
-  if gen_test_operation {
-    generate_test_operation(&patch_operations)
+  let patch_operations = collector.operations;
+  let patch_data = if options.gen_test_operation {
+    generate_test_operation(patch_operations, options.test_value)
   } else {
-    PatchData::CommonPatch(patch_operations)
-  }
+    PatchData::CommonPatch(patch_operations.into_iter().map(|(x, _)| x).collect())
+  };
+  (patch_data, collector.skipped_appends)
 }
 
-fn generate_test_operation(patch_operations: &Vec<Value>) -> PatchData {
+/// 为每个操作生成 test operation，with_value 为 true 时 test 同时检查原始值
+fn generate_test_operation(patch_operations: Vec<(Value, Value)>, with_value: bool) -> PatchData {
   let mut patch_batch = Vec::new();
 
-  for patch_operation in patch_operations {
-    patch_batch.push(Vec::from([
-      json!({
-        "op": "test",
-        "path": patch_operation["path"].as_str().unwrap(),
-      }),
-      patch_operation.clone(),
-    ]));
+  for (patch_operation, original_value) in patch_operations {
+    let mut test_operation = json!({
+      "op": "test",
+      "path": patch_operation["path"].as_str().unwrap(),
+    });
+    if with_value {
+      test_operation["value"] = original_value;
+    }
+    patch_batch.push(Vec::from([test_operation, patch_operation]));
   }
 
   PatchData::BatchesPatch(patch_batch)
 }
 
-/// 对外主方法：输入判断是否为JSON patch的布尔值、Value、文件后缀、PatternConfig，输出 patch 数组
+/// 去掉生成时添加的前缀，还原原始值
+fn strip_marker(value: &Value, marker: &Marker) -> Value {
+  match value {
+    Value::String(string_value) => Value::String(
+      marker
+        .strip(string_value)
+        .unwrap_or(string_value)
+        .to_string(),
+    ),
+    Value::Array(array_value) => Value::Array(
+      array_value
+        .iter()
+        .map(|x| strip_marker(x, marker))
+        .collect(),
+    ),
+    _ => value.clone(),
+  }
+}
+
+/// 提取 patch 中每个待翻译字符串的 JSON Pointer 及去掉前缀后的原始字符串
+/// 数组值会按下标展开，test operation 会被忽略
+pub fn marked_strings(patch_data: &PatchData, marker: &Marker) -> Vec<(String, String)> {
+  let mut strings = Vec::new();
+  for patch_operation in patch_data.operations() {
+    if patch_operation["op"] == "test" {
+      continue;
+    }
+    let path = patch_operation["path"].as_str().unwrap_or_default();
+    let mut record = |pointer: String, value: &str| {
+      let original = marker.strip(value).unwrap_or(value);
+      strings.push((pointer, original.to_string()));
+    };
+    match &patch_operation["value"] {
+      Value::String(value) => record(path.to_string(), value),
+      Value::Array(values) => {
+        for (index, value) in values.iter().enumerate() {
+          if let Value::String(value) = value {
+            record(format!("{}/{}", path, index), value);
+          }
+        }
+      }
+      _ => {}
+    }
+  }
+  strings
+}
+
+/// 查找源文件中已带有翻译标记的待翻译字符串，返回（JSON Pointer, 字符串）
+/// 遍历方式与生成 patch 时相同，只是选出生成时会被跳过的已标记字符串
+pub fn find_marked_strings(
+  is_patch: bool,
+  json_value: &Value,
+  file_extension: &str,
+  relative_path: &Path,
+  pattern_config: &PatternConfig,
+) -> Vec<(String, String)> {
+  let mut strings = Vec::new();
+  if let Some(pattern_set) = pattern_config.get_pattern_set(file_extension) {
+    let marker = pattern_config.resolve_marker(file_extension, relative_path);
+    if is_patch {
+      find_marked_in_patch(json_value, pattern_set, marker, &mut strings);
+    } else {
+      find_marked_in_json(json_value, "", pattern_set, marker, &mut strings);
+    }
+  }
+  strings
+}
+
+/// 递归遍历 JSON，记录匹配正则且已带标记的字符串
+fn find_marked_in_json(
+  json_value: &Value,
+  json_pointer: &str,
+  pattern_set: &PatternSet,
+  marker: &Marker,
+  strings: &mut Vec<(String, String)>,
+) {
+  match json_value {
+    Value::String(string_value)
+      if pattern_set.is_match(json_pointer) && marker.is_marked(string_value) =>
+    {
+      strings.push((json_pointer.to_string(), string_value.clone()));
+    }
+    Value::Array(array_value) => {
+      let is_match = pattern_set.is_match(json_pointer);
+      for (index, value) in array_value.iter().enumerate() {
+        let next_pointer = format!("{}/{}", json_pointer, index);
+        match value {
+          // 整个数组匹配时检查其中的字符串元素
+          Value::String(string_value) if is_match => {
+            if marker.is_marked(string_value) {
+              strings.push((next_pointer, string_value.clone()));
+            }
+          }
+          _ if is_match => {}
+          _ => find_marked_in_json(value, &next_pointer, pattern_set, marker, strings),
+        }
+      }
+    }
+    Value::Object(object_value) => {
+      for (key, value) in object_value {
+        let next_pointer = format!("{}/{}", json_pointer, escape_pointer_token(key));
+        find_marked_in_json(value, &next_pointer, pattern_set, marker, strings);
+      }
+    }
+    _ => {}
+  }
+}
+
+/// 遍历 patch 文件中的操作，检查 add/replace 操作的值
+fn find_marked_in_patch(
+  json_value: &Value,
+  pattern_set: &PatternSet,
+  marker: &Marker,
+  strings: &mut Vec<(String, String)>,
+) {
+  match json_value {
+    Value::Array(entries) => {
+      for entry in entries {
+        find_marked_in_patch(entry, pattern_set, marker, strings);
+      }
+    }
+    Value::Object(object_value) if is_patch_operation(object_value) => {
+      if matches!(object_value["op"].as_str(), Some("add" | "replace"))
+        && let (Some(Value::String(path)), Some(value)) =
+          (object_value.get("path"), object_value.get("value"))
+      {
+        find_marked_in_json(value, path, pattern_set, marker, strings);
+      }
+    }
+    _ => {}
+  }
+}
+
+/// 去掉 patch 文件中所有操作值的前缀，返回（去掉前缀的字符串数，未带前缀的字符串数）
+/// patch_value 可以是操作数组，也可以是批次数组（Vec<Vec<Value>>）
+pub fn strip_patch_markers(patch_value: &mut Value, marker: &Marker) -> (usize, usize) {
+  let mut counts = (0, 0);
+  match patch_value {
+    Value::Array(entries) => {
+      for entry in entries {
+        let (stripped, untouched) = strip_patch_markers(entry, marker);
+        counts.0 += stripped;
+        counts.1 += untouched;
+      }
+    }
+    Value::Object(patch_operation) => {
+      if let Some(value) = patch_operation.get_mut("value") {
+        strip_value_markers(value, marker, &mut counts);
+      }
+    }
+    _ => {}
+  }
+  counts
+}
+
+/// 原地去掉字符串或字符串数组中的前缀，并计数
+fn strip_value_markers(value: &mut Value, marker: &Marker, counts: &mut (usize, usize)) {
+  match value {
+    Value::String(string_value) => match marker.strip(string_value) {
+      Some(stripped) => {
+        *string_value = stripped.to_string();
+        counts.0 += 1;
+      }
+      None => counts.1 += 1,
+    },
+    Value::Array(array_value) => {
+      for x in array_value {
+        strip_value_markers(x, marker, counts);
+      }
+    }
+    _ => {}
+  }
+}
+
+/// 生成能把原始字符串恢复回来的反向 patch
+/// 提供 source 时从源文件中取原始值，否则去掉 patch 值中的前缀
+pub fn generate_reverse_patch(
+  patch_data: &PatchData,
+  source: Option<&Value>,
+  marker: &Marker,
+) -> PatchData {
+  let reverse_operations = patch_data
+    .operations()
+    .into_iter()
+    .filter(|patch_operation| patch_operation["op"] != "test")
+    .map(|patch_operation| {
+      let path = patch_operation["path"].as_str().unwrap_or_default();
+      let original_value = source
+        .and_then(|x| x.pointer(path))
+        .cloned()
+        .unwrap_or_else(|| strip_marker(&patch_operation["value"], marker));
+      json!({
+        "op": "replace",
+        "path": path,
+        "value": original_value
+      })
+    })
+    .collect();
+
+  PatchData::CommonPatch(reverse_operations)
+}
+
+/// 检查路径在 JSON 中是否经过纯数字的对象键（如 "0"）
+/// 这类键容易被 patch 应用方误认为数组下标
+pub fn has_numeric_object_key(json_value: &Value, json_pointer: &str) -> bool {
+  let mut current = json_value;
+  for segment in json_pointer.split('/').skip(1) {
+    let segment = &unescape_pointer_token(segment);
+    match current {
+      Value::Object(object_value) => {
+        if !segment.is_empty() && segment.chars().all(|c| c.is_ascii_digit()) {
+          return true;
+        }
+        match object_value.get(segment) {
+          Some(value) => current = value,
+          None => return false,
+        }
+      }
+      Value::Array(array_value) => match segment
+        .parse::<usize>()
+        .ok()
+        .and_then(|i| array_value.get(i))
+      {
+        Some(value) => current = value,
+        None => return false,
+      },
+      _ => return false,
+    }
+  }
+  false
+}
+
+/// 将 patch 应用到 JSON 上，返回所有无法应用的操作的说明
+/// 仅支持生成器会产生的 test/replace/add 操作，带 value 的 test 会比较值
+pub fn apply_patch(json_value: &mut Value, patch_data: &PatchData) -> Vec<String> {
+  let mut failures = Vec::new();
+
+  for patch_operation in patch_data.operations() {
+    let op = patch_operation["op"].as_str().unwrap_or_default();
+    let path = patch_operation["path"].as_str().unwrap_or_default();
+
+    match op {
+      // 带 value 时检查值是否相同，否则只检查路径是否存在
+      "test" => match (json_value.pointer(path), patch_operation.get("value")) {
+        (None, _) => failures.push(format!(
+          "test: path \"{}\" does not exist",
+          pointer_to_human(path)
+        )),
+        (Some(actual), Some(expected)) if actual != expected => failures.push(format!(
+          "test: value at \"{}\" is {}, expected {}",
+          pointer_to_human(path),
+          actual,
+          expected
+        )),
+        _ => {}
+      },
+      "replace" => match json_value.pointer_mut(path) {
+        Some(target) => {
+          *target = patch_operation["value"].clone();
+          if json_value.pointer(path) != Some(&patch_operation["value"]) {
+            failures.push(format!(
+              "replace: value at \"{}\" was not replaced",
+              pointer_to_human(path)
+            ));
+          }
+        }
+        None => failures.push(format!(
+          "replace: path \"{}\" does not exist",
+          pointer_to_human(path)
+        )),
+      },
+      "add" => {
+        let (parent_path, key) = path.rsplit_once('/').unwrap_or(("", path));
+        let key = unescape_pointer_token(key);
+        match json_value.pointer_mut(parent_path) {
+          Some(Value::Object(object_value)) => {
+            object_value.insert(key, patch_operation["value"].clone());
+          }
+          Some(Value::Array(array_value)) => match key.parse::<usize>() {
+            Ok(index) if index <= array_value.len() => {
+              array_value.insert(index, patch_operation["value"].clone())
+            }
+            _ if key == "-" => array_value.push(patch_operation["value"].clone()),
+            _ => failures.push(format!(
+              "add: invalid array index in \"{}\"",
+              pointer_to_human(path)
+            )),
+          },
+          _ => failures.push(format!(
+            "add: parent of \"{}\" does not exist",
+            pointer_to_human(path)
+          )),
+        }
+      }
+      _ => failures.push(format!(
+        "{}: unsupported operation at \"{}\"",
+        op,
+        pointer_to_human(path)
+      )),
+    }
+  }
+
+  failures
+}
+
+/// 对外主方法：输入判断是否为JSON patch的布尔值、Value、文件后缀、相对路径、PatternConfig，输出 patch 数组
+/// 翻译标记按 PatternConfig::resolve_marker 的优先级确定，comments 仅对非patch文件生效
 pub fn generate_patch(
   is_patch: bool,
   json_value: &Value,
   file_extension: &str,
+  relative_path: &Path,
   pattern_config: &PatternConfig,
-  gen_test_operation: bool,
+  comments: Option<&Comments>,
+  options: &PatchOptions,
 ) -> PatchData {
+  let marker = pattern_config.resolve_marker(file_extension, relative_path);
   match pattern_config.get_pattern_set(file_extension) {
     Some(pattern_set) => {
       if is_patch {
-        process_json_patch(json_value, pattern_set.get_regex(), gen_test_operation)
+        let (patch_data, skipped_appends) =
+          process_json_patch(json_value, pattern_set, marker, options);
+        for path in skipped_appends {
+          log_warn!(
+            "Skipped add to {} in {}: values appended with \"-\" have no stable path to translate",
+            path,
+            relative_path.display()
+          );
+        }
+        patch_data
       } else {
-        process_json(json_value, pattern_set.get_regex(), gen_test_operation)
+        process_json(json_value, pattern_set, comments, marker, options)
       }
     }
     // unreachale???
     None => PatchData::CommonPatch(Vec::new()),
   }
 }
+
+/// 对外方法：直接使用给定的 PatternSet 处理已解析的 Value，不查找后缀名和配置
+/// 使用默认的翻译标记前缀 "(T) "，gen_test 为 true 时为每个操作生成 test operation
+pub fn generate_from_value(
+  json_value: &Value,
+  pattern_set: &PatternSet,
+  is_patch: bool,
+  gen_test: bool,
+) -> PatchData {
+  let marker = Marker::from_prefix(DEFAULT_PREFIX);
+  let options = PatchOptions {
+    gen_test_operation: gen_test,
+    ..PatchOptions::default()
+  };
+  if is_patch {
+    process_json_patch(json_value, pattern_set, &marker, &options).0
+  } else {
+    process_json(json_value, pattern_set, None, &marker, &options)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn pattern_set(patterns: &[&str]) -> PatternSet {
+    PatternSet::new(
+      patterns.iter().map(|x| x.to_string()).collect(),
+      Vec::new(),
+      false,
+    )
+    .unwrap()
+  }
+
+  fn paths(patch_data: &PatchData) -> Vec<&str> {
+    patch_data
+      .operations()
+      .into_iter()
+      .map(|x| x["path"].as_str().unwrap())
+      .collect()
+  }
+
+  #[test]
+  fn patch_applies_to_a_fresh_copy_of_the_source() {
+    let source = json!({
+      "title": "Sword",
+      "tags": ["sharp", "metal"],
+      "a/b~c": "escaped",
+      "nested": {"description": "Long"}
+    });
+    let patch_data = generate_from_value(&source, &pattern_set(&["^/"]), false, true);
+    let mut source_copy = source.clone();
+    assert_eq!(
+      apply_patch(&mut source_copy, &patch_data),
+      Vec::<String>::new()
+    );
+    assert_eq!(
+      source_copy,
+      json!({
+        "title": "(T) Sword",
+        "tags": ["(T) sharp", "(T) metal"],
+        "a/b~c": "(T) escaped",
+        "nested": {"description": "(T) Long"}
+      })
+    );
+    // 路径无法解析时报告失败
+    let mut other_source = json!({"tags": []});
+    assert!(!apply_patch(&mut other_source, &patch_data).is_empty());
+  }
+
+  #[test]
+  fn pointer_tokens_round_trip() {
+    for key in ["plain", "a/b", "a~b", "~1", "/~0/", ""] {
+      let token = escape_pointer_token(key);
+      assert!(!token.contains('/'), "{}", token);
+      assert_eq!(unescape_pointer_token(&token), key);
+    }
+    assert_eq!(escape_pointer_token("a~/b"), "a~0~1b");
+    assert_eq!(path_to_pointer("items/a~b.object"), "/items/a~0b.object");
+  }
+
+  #[test]
+  fn generated_pointers_escape_keys() {
+    let source = json!({"a/b": {"c~d": "x"}});
+    let patch_data = generate_from_value(&source, &pattern_set(&["^/a~1b/c~0d$"]), false, false);
+    assert_eq!(paths(&patch_data), ["/a~1b/c~0d"]);
+    assert!(validate_patch(&patch_data).is_ok());
+  }
+
+  #[test]
+  fn human_pointers_unescape_and_quote() {
+    assert_eq!(pointer_to_human("/a/b~1c/0"), "a.b/c[0]");
+    assert_eq!(pointer_to_human("/a.b/"), r#"["a.b"][""]"#);
+    let source = json!({"items": {"0": {"name": "x"}}, "list": [{"name": "y"}]});
+    assert_eq!(
+      pointer_to_human_in(&source, "/items/0/name"),
+      r#"items["0"].name"#
+    );
+    assert_eq!(pointer_to_human_in(&source, "/list/0/name"), "list[0].name");
+  }
+
+  #[test]
+  fn numeric_object_keys_are_detected() {
+    let source = json!({"items": {"0": {"name": "x"}}, "list": [{"name": "y"}]});
+    assert!(has_numeric_object_key(&source, "/items/0/name"));
+    assert!(!has_numeric_object_key(&source, "/list/0/name"));
+  }
+
+  #[test]
+  fn truncate_keeps_the_first_entries() {
+    let source = json!({"a": "1", "b": "2", "c": "3"});
+    let mut patch_data = generate_from_value(&source, &pattern_set(&["^/"]), false, false);
+    patch_data.truncate(2);
+    assert_eq!(paths(&patch_data), ["/a", "/b"]);
+    let mut batches = generate_from_value(&source, &pattern_set(&["^/"]), false, true);
+    batches.truncate(1);
+    assert_eq!(batches.entry_count(), 1);
+    assert_eq!(batches.operation_count(), 1);
+  }
+
+  #[test]
+  fn op_kinds_follow_the_base_file() {
+    let source = json!({"a": "1", "b": "2"});
+    let mut patch_data = generate_from_value(&source, &pattern_set(&["^/"]), false, false);
+    assert_eq!(patch_data.set_op_kinds_from_base(&json!({"a": "old"})), 1);
+    let ops: Vec<&str> = patch_data
+      .operations()
+      .into_iter()
+      .map(|x| x["op"].as_str().unwrap())
+      .collect();
+    assert_eq!(ops, ["replace", "add"]);
+  }
+
+  #[test]
+  fn prefix_paths_prefixes_path_and_from() {
+    let mut patch_data = PatchData::CommonPatch(vec![
+      json!({"op": "replace", "path": "/a", "value": "x"}),
+      json!({"op": "move", "from": "/b", "path": "/c"}),
+    ]);
+    patch_data.prefix_paths(&path_to_pointer("items/sword.item"));
+    assert_eq!(
+      patch_data.operations()[1],
+      &json!({"op": "move", "from": "/items/sword.item/b", "path": "/items/sword.item/c"})
+    );
+  }
+
+  #[test]
+  fn patch_files_only_translate_op_values() {
+    let patch_file = json!([
+      {"op": "add", "path": "/description", "value": "Hello"},
+      {"op": "replace", "path": "/info", "value": {"title": "Nested"}},
+      {"op": "test", "path": "/description", "value": "Ignored"},
+      {"op": "add", "path": "/list/-", "value": {"description": "Appended"}}
+    ]);
+    let marker = Marker::from_prefix(DEFAULT_PREFIX);
+    let (patch_data, skipped_appends) = process_json_patch(
+      &patch_file,
+      &pattern_set(&["^/description$", "^/info/title$", "^/list/.*/description$"]),
+      &marker,
+      &PatchOptions::default(),
+    );
+    assert_eq!(paths(&patch_data), ["/description", "/info/title"]);
+    assert_eq!(skipped_appends, ["/list/-"]);
+  }
+
+  #[test]
+  fn validate_patch_rejects_malformed_operations() {
+    assert!(
+      validate_patch(&PatchData::CommonPatch(vec![
+        json!({"op": "replace", "path": "/a", "value": 1})
+      ]))
+      .is_ok()
+    );
+    for patch_operation in [
+      json!({"op": "replace", "path": "a", "value": 1}),
+      json!({"op": "replace", "path": "/a"}),
+      json!({"op": "rename", "path": "/a", "value": 1}),
+      json!({"op": "replace", "path": "/a~2", "value": 1}),
+    ] {
+      let patch_data = PatchData::CommonPatch(vec![patch_operation.clone()]);
+      assert!(validate_patch(&patch_data).is_err(), "{}", patch_operation);
+    }
+    assert!(validate_patch(&PatchData::BatchesPatch(vec![Vec::new()])).is_err());
+  }
+
+  #[test]
+  fn strings_equal_to_their_key_are_skipped() {
+    let json_value = json!({"sword": "sword", "title": "Iron Sword", "name": "name"});
+    let marker = Marker::from_prefix(DEFAULT_PREFIX);
+    let patterns = pattern_set(&["^/[a-z]+$"]);
+    let options = PatchOptions::default();
+    let kept = process_json(&json_value, &patterns, None, &marker, &options);
+    assert_eq!(paths(&kept), ["/sword", "/title", "/name"]);
+    let skipped = process_json(
+      &json_value,
+      &patterns.with_skip_if_equals_key(true),
+      None,
+      &marker,
+      &options,
+    );
+    assert_eq!(paths(&skipped), ["/title"]);
+  }
+
+  #[test]
+  fn skip_if_equals_key_covers_array_elements_and_patch_files() {
+    let marker = Marker::from_prefix(DEFAULT_PREFIX);
+    let patterns = pattern_set(&["^/tags$", "^/names/[0-9]+$"]).with_skip_if_equals_key(true);
+    let options = PatchOptions::default();
+
+    // 数组元素与数组的键比较
+    let json_value = json!({"tags": ["tags", "Sharp"], "names": ["names", "Blade"]});
+    let patch_data = process_json(&json_value, &patterns, None, &marker, &options);
+    assert_eq!(
+      patch_data.operations(),
+      [
+        &json!({"op": "replace", "path": "/tags", "value": ["tags", "(T) Sharp"]}),
+        &json!({"op": "replace", "path": "/names/1", "value": "(T) Blade"})
+      ]
+    );
+
+    let patch_file = json!([
+      {"op": "add", "path": "/tags", "value": ["tags", "Sharp"]},
+      {"op": "replace", "path": "/names/0", "value": "names"}
+    ]);
+    let (patch_data, _) = process_json_patch(&patch_file, &patterns, &marker, &options);
+    assert_eq!(
+      patch_data.operations(),
+      [&json!({"op": "replace", "path": "/tags", "value": ["tags", "(T) Sharp"]})]
+    );
+  }
+
+  #[test]
+  fn test_operations_compare_values() {
+    let mut source = json!({"title": "Sword"});
+    let patch_data = PatchData::CommonPatch(vec![
+      json!({"op": "test", "path": "/title"}),
+      json!({"op": "test", "path": "/title", "value": "Sword"}),
+    ]);
+    assert_eq!(apply_patch(&mut source, &patch_data), Vec::<String>::new());
+
+    let patch_data = PatchData::CommonPatch(vec![
+      json!({"op": "test", "path": "/title", "value": "Axe"}),
+      json!({"op": "test", "path": "/missing"}),
+    ]);
+    assert_eq!(
+      apply_patch(&mut source, &patch_data),
+      [
+        "test: value at \"title\" is \"Sword\", expected \"Axe\"",
+        "test: path \"missing\" does not exist"
+      ]
+    );
+  }
+}
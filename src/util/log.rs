@@ -0,0 +1,94 @@
+use std::{
+  fmt,
+  sync::atomic::{AtomicBool, AtomicU8, Ordering},
+};
+
+/// 输出级别，错误信息在任何级别下都会输出
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+  /// 只输出错误
+  Quiet = 0,
+  /// 输出 [INFO] 和 [WARN]
+  Normal = 1,
+  /// 额外输出更多细节
+  Verbose = 2,
+  /// 额外输出每个文件的处理结果
+  Trace = 3,
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(LogLevel::Normal as u8);
+
+/// 是否把信息输出到 stderr
+static TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// 根据命令行参数得到输出级别
+pub fn level_from_flags(verbose: u8, quiet: bool) -> LogLevel {
+  match (quiet, verbose) {
+    (true, _) => LogLevel::Quiet,
+    (false, 0) => LogLevel::Normal,
+    (false, 1) => LogLevel::Verbose,
+    (false, _) => LogLevel::Trace,
+  }
+}
+
+/// 设置全局输出级别
+pub fn set_level(level: LogLevel) {
+  LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+/// 当前级别下是否输出该级别的信息
+pub fn enabled(level: LogLevel) -> bool {
+  LEVEL.load(Ordering::Relaxed) >= level as u8
+}
+
+/// 把信息输出到 stderr，stdout 只留给数据输出（如 gen --stdin 输出的 patch）
+pub fn set_stderr(to_stderr: bool) {
+  TO_STDERR.store(to_stderr, Ordering::Relaxed);
+}
+
+/// 按当前设置输出一条带标签的信息
+pub fn write(tag: &str, args: fmt::Arguments) {
+  if TO_STDERR.load(Ordering::Relaxed) {
+    eprintln!("{} {}", tag, args);
+  } else {
+    println!("{} {}", tag, args);
+  }
+}
+
+/// 输出 [INFO] 信息
+macro_rules! log_info {
+  ($($arg:tt)*) => {
+    if $crate::util::log::enabled($crate::util::log::LogLevel::Normal) {
+      $crate::util::log::write("[INFO]", format_args!($($arg)*));
+    }
+  };
+}
+
+/// 输出 [WARN] 信息
+macro_rules! log_warn {
+  ($($arg:tt)*) => {
+    if $crate::util::log::enabled($crate::util::log::LogLevel::Normal) {
+      $crate::util::log::write("[WARN]", format_args!($($arg)*));
+    }
+  };
+}
+
+/// 输出 -v 时才需要的 [INFO] 信息
+macro_rules! log_verbose {
+  ($($arg:tt)*) => {
+    if $crate::util::log::enabled($crate::util::log::LogLevel::Verbose) {
+      $crate::util::log::write("[INFO]", format_args!($($arg)*));
+    }
+  };
+}
+
+/// 输出 -vv 时才需要的 [INFO] 信息
+macro_rules! log_trace {
+  ($($arg:tt)*) => {
+    if $crate::util::log::enabled($crate::util::log::LogLevel::Trace) {
+      $crate::util::log::write("[INFO]", format_args!($($arg)*));
+    }
+  };
+}
+
+pub(crate) use {log_info, log_trace, log_verbose, log_warn};
@@ -0,0 +1,62 @@
+use std::{sync::Mutex, thread};
+
+/// 在多个线程中并行处理 items，结果保持原有顺序
+/// 各线程从共享队列中逐个领取任务，耗时不均的文件不会让某个线程拖慢整体
+pub fn par_map<T: Send, R: Send>(items: Vec<T>, f: impl Fn(T) -> R + Sync) -> Vec<R> {
+  let threads = thread::available_parallelism()
+    .map(|n| n.get())
+    .unwrap_or(1);
+  if threads <= 1 || items.len() <= 1 {
+    return items.into_iter().map(f).collect();
+  }
+
+  let item_count = items.len();
+  let queue = Mutex::new(items.into_iter().enumerate());
+  let (queue, f) = (&queue, &f);
+  let mut results: Vec<Option<R>> = (0..item_count).map(|_| None).collect();
+  thread::scope(|scope| {
+    let handles: Vec<_> = (0..threads.min(item_count))
+      .map(|_| {
+        scope.spawn(move || {
+          let mut finished = Vec::new();
+          loop {
+            // 先释放锁再处理，避免处理期间阻塞其他线程领取任务
+            let next = queue.lock().expect("[ERROR] Work queue poisoned!").next();
+            let Some((index, item)) = next else {
+              break;
+            };
+            finished.push((index, f(item)));
+          }
+          finished
+        })
+      })
+      .collect();
+    for handle in handles {
+      for (index, result) in handle.join().expect("[ERROR] Worker thread panicked!") {
+        results[index] = Some(result);
+      }
+    }
+  });
+  results.into_iter().flatten().collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::time::Duration;
+
+  #[test]
+  fn results_match_sequential_order() {
+    let items: Vec<u64> = (0..200).collect();
+    let sequential: Vec<String> = items.iter().map(|x| format!("{}", x * x)).collect();
+    let parallel = par_map(items, |x| {
+      // 让前面的任务耗时更长，打乱完成顺序
+      if x < 4 {
+        thread::sleep(Duration::from_millis(20));
+      }
+      format!("{}", x * x)
+    });
+    assert_eq!(parallel, sequential);
+    assert!(par_map(Vec::<u8>::new(), |x| x).is_empty());
+  }
+}
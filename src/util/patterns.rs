@@ -1,62 +1,777 @@
-use anyhow::Result;
-use regex::RegexSet;
-use serde::Deserialize;
-use std::collections::HashMap;
+use anyhow::{Context, Result, bail};
+use clap::ValueEnum;
+use regex::{Regex, RegexSet, RegexSetBuilder};
+use serde::{Deserialize, Deserializer, de::Error as _};
+use serde_json::Value;
+use std::{collections::HashMap, path::Path};
+
+use crate::util::{glob, json5};
 
 /// 配置模式集合，包含原始模式和编译后的正则表达式
+/// 以 "^/<字面量>/" 或 "^/<字面量>$" 开头的正则按指针首段分组，
+/// 匹配时只需检查指针首段对应的组和无法分组的正则
 #[derive(Debug)]
 pub struct PatternSet {
-  /// 编译后的正则
-  compiled_regex: Option<RegexSet>,
+  /// 包含的原始正则
+  patterns: Vec<String>,
+  /// 按指针首段字面量分组的正则
+  grouped: HashMap<String, PatternGroup>,
+  /// 无法确定指针首段的正则
+  fallback: Option<PatternGroup>,
+  /// 排除的正则，匹配的指针不会生成操作
+  exclude: Option<RegexSet>,
+  /// 是否忽略大小写匹配
+  case_insensitive: bool,
+  /// 字符串与其键（指针最后一个非下标片段，数组元素即数组的键）相同时不生成操作
+  skip_if_equals_key: bool,
+  /// 可能永远无法匹配的正则的提示
+  diagnostics: Vec<String>,
+}
+
+/// 一组编译后的正则，及每个正则在原始列表中的下标
+#[derive(Debug)]
+struct PatternGroup {
+  set: RegexSet,
+  indices: Vec<usize>,
+}
+
+impl PatternGroup {
+  /// 匹配的正则在原始列表中的最小下标
+  fn first_match(&self, json_pointer: &str) -> Option<usize> {
+    self
+      .set
+      .matches(json_pointer)
+      .iter()
+      .map(|i| self.indices[i])
+      .min()
+  }
 }
 
 impl PatternSet {
   /// 创建新的PatternSet并编译正则表达式
-  pub fn new(string_patterns: Vec<String>) -> Result<Self> {
-    let compiled_regex = if string_patterns.is_empty() {
+  /// case_insensitive 为 true 时所有正则都忽略大小写
+  pub fn new(
+    string_patterns: Vec<String>,
+    exclude_patterns: Vec<String>,
+    case_insensitive: bool,
+  ) -> Result<Self> {
+    let diagnostics = string_patterns
+      .iter()
+      .chain(&exclude_patterns)
+      .filter_map(|pattern| diagnose_pattern(pattern))
+      .collect();
+
+    let build = |patterns: Vec<&String>| {
+      RegexSetBuilder::new(patterns)
+        .case_insensitive(case_insensitive)
+        .build()
+    };
+
+    let build_group = |patterns: Vec<(usize, &String)>| {
+      let (indices, patterns): (Vec<usize>, Vec<&String>) = patterns.into_iter().unzip();
+      build(patterns).map(|set| PatternGroup { set, indices })
+    };
+
+    let mut grouped_patterns: HashMap<String, Vec<(usize, &String)>> = HashMap::new();
+    let mut fallback_patterns = Vec::new();
+    for (index, pattern) in string_patterns.iter().enumerate() {
+      match literal_first_segment(pattern) {
+        Some(segment) => grouped_patterns
+          .entry(normalize_segment(segment, case_insensitive))
+          .or_default()
+          .push((index, pattern)),
+        None => fallback_patterns.push((index, pattern)),
+      }
+    }
+
+    let mut grouped = HashMap::new();
+    for (segment, patterns) in grouped_patterns {
+      grouped.insert(segment, build_group(patterns)?);
+    }
+    let fallback = if fallback_patterns.is_empty() {
       None
     } else {
-      Some(RegexSet::new(&string_patterns)?)
+      Some(build_group(fallback_patterns)?)
     };
 
-    Ok(Self { compiled_regex })
+    let exclude = if exclude_patterns.is_empty() {
+      None
+    } else {
+      Some(build(exclude_patterns.iter().collect())?)
+    };
+
+    Ok(Self {
+      patterns: string_patterns,
+      grouped,
+      fallback,
+      exclude,
+      case_insensitive,
+      skip_if_equals_key: false,
+      diagnostics,
+    })
+  }
+
+  /// 设置字符串与其键相同时是否跳过，用于避免翻译与显示名相同的内部 id
+  pub fn with_skip_if_equals_key(mut self, skip_if_equals_key: bool) -> Self {
+    self.skip_if_equals_key = skip_if_equals_key;
+    self
+  }
+
+  /// 字符串与其键相同时是否跳过
+  pub fn skip_if_equals_key(&self) -> bool {
+    self.skip_if_equals_key
+  }
+
+  /// 返回第一个匹配该 JSON Pointer 的正则的下标及原始字符串，用于排查配置
+  /// 被排除的指针返回 None
+  pub fn explain(&self, json_pointer: &str) -> Option<(usize, &str)> {
+    if self
+      .exclude
+      .as_ref()
+      .is_some_and(|set| set.is_match(json_pointer))
+    {
+      return None;
+    }
+    let index = [self.pointer_group(json_pointer), self.fallback.as_ref()]
+      .into_iter()
+      .flatten()
+      .filter_map(|group| group.first_match(json_pointer))
+      .min()?;
+    Some((index, &self.patterns[index]))
+  }
+
+  /// 指针首段对应的正则组
+  fn pointer_group(&self, json_pointer: &str) -> Option<&PatternGroup> {
+    first_segment(json_pointer).and_then(|segment| {
+      self
+        .grouped
+        .get(&normalize_segment(segment, self.case_insensitive))
+    })
+  }
+
+  /// 可能写错的正则的提示（仅供参考，不影响匹配）
+  pub fn diagnostics(&self) -> &[String] {
+    &self.diagnostics
+  }
+
+  /// 正则数量（包括排除的正则）
+  pub fn pattern_count(&self) -> usize {
+    self.patterns.len() + self.exclude.as_ref().map_or(0, RegexSet::len)
+  }
+
+  /// 检查该 JSON Pointer 是否匹配包含的正则，且不匹配排除的正则
+  pub fn is_match(&self, json_pointer: &str) -> bool {
+    let grouped_match = self
+      .pointer_group(json_pointer)
+      .is_some_and(|group| group.set.is_match(json_pointer));
+    let included = grouped_match
+      || self
+        .fallback
+        .as_ref()
+        .is_some_and(|group| group.set.is_match(json_pointer));
+    included
+      && !self
+        .exclude
+        .as_ref()
+        .is_some_and(|set| set.is_match(json_pointer))
+  }
+}
+
+/// 检查正则是否明显无法匹配 JSON Pointer，返回提示
+fn diagnose_pattern(pattern: &str) -> Option<String> {
+  // 指针总是以 '/' 开头（根指针为空字符串）
+  if !pattern.starts_with("^/") && pattern != "^$" {
+    return Some(format!(
+      "pattern \"{}\" is not anchored with \"^/\", JSON Pointers always start with '/'",
+      pattern
+    ));
+  }
+  // 指针中不会出现文件后缀名，通常是误写成了文件路径
+  let lowercase = pattern.to_lowercase();
+  if [".json", ".patch"]
+    .iter()
+    .any(|ext| lowercase.contains(ext))
+  {
+    return Some(format!(
+      "pattern \"{}\" contains a file extension, which never appears in a JSON Pointer",
+      pattern
+    ));
+  }
+  None
+}
+
+/// 忽略大小写时，分组的键统一转为小写
+fn normalize_segment(segment: &str, case_insensitive: bool) -> String {
+  if case_insensitive {
+    segment.to_lowercase()
+  } else {
+    segment.to_string()
   }
+}
+
+/// 获取 JSON Pointer 的首段
+fn first_segment(json_pointer: &str) -> Option<&str> {
+  json_pointer.strip_prefix('/')?.split('/').next()
+}
 
-  /// 获取正则
-  pub fn get_regex(&self) -> Option<&RegexSet> {
-    self.compiled_regex.as_ref()
+/// 获取正则能匹配的指针首段字面量，无法确定时返回None
+fn literal_first_segment(pattern: &str) -> Option<&str> {
+  let rest = pattern.strip_prefix("^/")?;
+  let end = rest
+    .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+    .unwrap_or(rest.len());
+  let segment = &rest[..end];
+  // 首段之后必须是 '/' 或结尾的 '$'，否则首段可能更长
+  let is_whole_segment = match &rest[end..] {
+    "$" => true,
+    remain => remain.starts_with('/'),
+  };
+  if segment.is_empty() || !is_whole_segment || has_top_level_alternation(pattern) {
+    return None;
+  }
+  Some(segment)
+}
+
+/// 检查正则是否包含顶层的 '|'（此时 '^' 不再约束整个正则）
+fn has_top_level_alternation(pattern: &str) -> bool {
+  let mut depth = 0usize;
+  let mut in_class = false;
+  let mut chars = pattern.chars();
+  while let Some(c) = chars.next() {
+    match c {
+      '\\' => {
+        chars.next();
+      }
+      '[' => in_class = true,
+      ']' => in_class = false,
+      '(' if !in_class => depth += 1,
+      ')' if !in_class => depth = depth.saturating_sub(1),
+      '|' if !in_class && depth == 0 => return true,
+      _ => {}
+    }
+  }
+  false
+}
+
+/// 默认的翻译标记前缀
+pub const DEFAULT_PREFIX: &str = "(T) ";
+
+/// 翻译标记，由模板中 "{}" 前后的部分组成，如 "(T) {}" 或 "^white;(T) {}"
+/// 另外可以识别其他标记前缀（如表示待校对的 "(R) "），带有这些前缀的字符串同样视为已处理
+#[derive(Debug, Clone, PartialEq)]
+pub struct Marker {
+  prefix: String,
+  suffix: String,
+  /// 同样视为已处理的其他标记前缀
+  recognized: Vec<String>,
+}
+
+impl Marker {
+  /// 从模板创建，模板必须包含且只包含一个 "{}"
+  pub fn from_template(template: &str) -> Result<Self> {
+    match template.split_once("{}") {
+      Some((prefix, suffix)) if !suffix.contains("{}") => Ok(Self {
+        prefix: prefix.to_string(),
+        suffix: suffix.to_string(),
+        recognized: Vec::new(),
+      }),
+      _ => bail!(
+        "[ERROR] Template \"{}\" must contain exactly one \"{{}}\"",
+        template
+      ),
+    }
+  }
+
+  /// 只有前缀的标记
+  pub fn from_prefix(prefix: &str) -> Self {
+    Self {
+      prefix: prefix.to_string(),
+      suffix: String::new(),
+      recognized: Vec::new(),
+    }
+  }
+
+  /// 添加同样视为已处理的标记前缀，空前缀会被忽略
+  pub fn with_recognized(mut self, prefixes: impl IntoIterator<Item = String>) -> Self {
+    for prefix in prefixes {
+      if !prefix.is_empty() && !self.recognized.contains(&prefix) {
+        self.recognized.push(prefix);
+      }
+    }
+    self
+  }
+
+  /// 标记的前缀部分
+  pub fn prefix(&self) -> &str {
+    &self.prefix
+  }
+
+  /// 为字符串添加标记
+  pub fn mark(&self, string_value: &str) -> String {
+    format!("{}{}{}", self.prefix, string_value, self.suffix)
+  }
+
+  /// 检查字符串是否已带有标记或任一其他标记前缀（空标记视为未标记）
+  pub fn is_marked(&self, string_value: &str) -> bool {
+    (!(self.prefix.is_empty() && self.suffix.is_empty()) && self.strip(string_value).is_some())
+      || self
+        .recognized
+        .iter()
+        .any(|prefix| string_value.starts_with(prefix.as_str()))
+  }
+
+  /// 去掉字符串的标记，未带有标记时返回 None
+  pub fn strip<'a>(&self, string_value: &'a str) -> Option<&'a str> {
+    string_value
+      .strip_prefix(self.prefix.as_str())?
+      .strip_suffix(self.suffix.as_str())
   }
 }
 
 /// 完整的模式配置，按文件扩展名组织
+/// 扩展名统一转为小写，查找时忽略大小写
 #[derive(Debug)]
 pub struct PatternConfig {
   patterns: HashMap<String, PatternSet>,
+  /// 配置中设置的纯数字对象键处理方式，None表示未设置
+  numeric_keys: Option<NumericKeyPolicy>,
+  /// 默认的翻译标记
+  marker: Marker,
+  /// 单独设置了模板的后缀名的翻译标记
+  markers: HashMap<String, Marker>,
+  /// 按相对路径 glob 设置的翻译标记，按配置顺序匹配
+  path_markers: Vec<(Regex, Marker)>,
+  /// 命令行指定的翻译标记，优先于所有配置
+  override_marker: Option<Marker>,
 }
 
 impl PatternConfig {
+  /// 从 JSON5 格式的正则配置文本创建PatternConfig（解析、反序列化并编译正则）
+  pub fn from_json5_str(s: &str) -> Result<Self> {
+    let raw_config =
+      json5::from_str::<RawPatternConfig>(s).context("Invalid regex config format")?;
+    Self::from_raw_config(raw_config)
+  }
+
   /// 从原始配置创建PatternConfig
+  /// 编译所有后缀名的正则后再报告错误，一次列出所有出错的后缀名
   pub fn from_raw_config(raw_config: RawPatternConfig) -> Result<Self> {
+    let RawPatternConfig {
+      prefix,
+      markers: recognized,
+      path_prefixes,
+      numeric_keys,
+      patterns: raw_patterns,
+    } = raw_config;
+    let marker = Marker::from_prefix(prefix.as_deref().unwrap_or(DEFAULT_PREFIX))
+      .with_recognized(recognized.clone());
     let mut patterns = HashMap::new();
-    for (file_extension, patterns_vec) in raw_config.patterns {
-      patterns.insert(file_extension, PatternSet::new(patterns_vec)?);
+    let mut markers = HashMap::new();
+    let mut errors = Vec::new();
+    let mut path_markers = Vec::new();
+    for RawPathPrefix { glob, prefix } in path_prefixes {
+      let pattern = format!(
+        "^{}(?:/.*)?$",
+        glob::glob_to_regex(glob.replace('\\', "/").trim_end_matches('/'))
+      );
+      match Regex::new(&pattern) {
+        Ok(regex) => path_markers.push((
+          regex,
+          Marker::from_prefix(&prefix).with_recognized(recognized.clone()),
+        )),
+        Err(e) => errors.push(format!("Invalid path glob \"{}\": {}", glob, e)),
+      }
+    }
+    for (file_extension, raw_pattern_set) in raw_patterns {
+      let file_extension = file_extension.to_lowercase();
+      let pattern_set = match raw_pattern_set {
+        RawPatternSet::List(include) => PatternSet::new(include, Vec::new(), false),
+        RawPatternSet::Rules(RawPatternRules {
+          include,
+          exclude,
+          case_insensitive,
+          skip_if_equals_key,
+          prefix: extension_prefix,
+          template,
+          markers: extension_recognized,
+        }) => {
+          // 单独设置了前缀、模板或其他标记前缀时使用该后缀名自己的标记
+          let extension_marker = match (extension_prefix, template) {
+            (Some(_), Some(_)) => {
+              errors.push(format!(
+                "Invalid template for \"{}\": prefix and template cannot be used together",
+                file_extension
+              ));
+              None
+            }
+            (Some(extension_prefix), None) => {
+              Some(Marker::from_prefix(&extension_prefix).with_recognized(recognized.clone()))
+            }
+            (None, Some(template)) => match Marker::from_template(&template) {
+              Ok(template_marker) => Some(template_marker.with_recognized(recognized.clone())),
+              Err(e) => {
+                errors.push(format!(
+                  "Invalid template for \"{}\": {}",
+                  file_extension,
+                  e.to_string().trim_start_matches("[ERROR] ")
+                ));
+                None
+              }
+            },
+            (None, None) => (!extension_recognized.is_empty()).then(|| marker.clone()),
+          };
+          if let Some(extension_marker) = extension_marker {
+            markers.insert(
+              file_extension.clone(),
+              extension_marker.with_recognized(extension_recognized),
+            );
+          }
+          PatternSet::new(include, exclude, case_insensitive)
+            .map(|pattern_set| pattern_set.with_skip_if_equals_key(skip_if_equals_key))
+        }
+      };
+      match pattern_set {
+        Ok(pattern_set) => {
+          patterns.insert(file_extension, pattern_set);
+        }
+        Err(e) => errors.push(format!("Invalid regex for \"{}\": {:#}", file_extension, e)),
+      }
     }
-    Ok(Self { patterns })
+    if !errors.is_empty() {
+      errors.sort();
+      bail!("[ERROR] {}", errors.join("\n[ERROR] "));
+    }
+    Ok(Self {
+      patterns,
+      numeric_keys,
+      marker,
+      markers,
+      path_markers,
+      override_marker: None,
+    })
+  }
+
+  /// 使用命令行指定的翻译标记前缀，覆盖配置中的所有前缀和模板
+  pub fn with_prefix_override(mut self, prefix: &str) -> Self {
+    let recognized = self.marker.recognized.clone();
+    self.override_marker = Some(Marker::from_prefix(prefix).with_recognized(recognized));
+    self
+  }
+
+  /// 所有后缀名的正则提示，按后缀名排序
+  pub fn diagnostics(&self) -> Vec<String> {
+    let mut extensions: Vec<&String> = self.patterns.keys().collect();
+    extensions.sort();
+    extensions
+      .into_iter()
+      .flat_map(|ext| {
+        self.patterns[ext]
+          .diagnostics()
+          .iter()
+          .map(move |warning| format!("{}: {}", ext, warning))
+      })
+      .collect()
+  }
+
+  /// 获取文件使用的翻译标记，relative_path 为相对于输入目录的路径
+  /// 优先级从高到低：
+  /// 1. 命令行的 --prefix
+  /// 2. 配置中 path_prefixes 里第一个匹配相对路径的 glob
+  /// 3. 该后缀名的 prefix / template
+  /// 4. 配置顶层的 prefix
+  /// 5. 内置的默认前缀 "(T) "（已在 4 中作为缺省值）
+  pub fn resolve_marker(&self, ext: &str, relative_path: &Path) -> &Marker {
+    if let Some(marker) = &self.override_marker {
+      return marker;
+    }
+    let path = relative_path.to_string_lossy().replace('\\', "/");
+    self
+      .path_markers
+      .iter()
+      .find(|(regex, _)| regex.is_match(&path))
+      .map(|(_, marker)| marker)
+      .or_else(|| self.markers.get(&ext.to_lowercase()))
+      .unwrap_or(&self.marker)
+  }
+
+  /// 获取文件使用的翻译标记前缀，优先级见 resolve_marker
+  pub fn resolve_prefix(&self, ext: &str, relative_path: &Path) -> &str {
+    self.resolve_marker(ext, relative_path).prefix()
+  }
+
+  /// 配置中设置的纯数字对象键处理方式，命令行的 --numeric-keys 优先
+  pub fn numeric_key_policy(&self) -> Option<NumericKeyPolicy> {
+    self.numeric_keys
+  }
+
+  /// 所有配置了正则的后缀名（小写），按字母顺序排列
+  pub fn extensions(&self) -> impl Iterator<Item = &str> {
+    let mut extensions: Vec<&str> = self.patterns.keys().map(String::as_str).collect();
+    extensions.sort();
+    extensions.into_iter()
+  }
+
+  /// 所有后缀名的正则总数（包括排除的正则）
+  pub fn pattern_count(&self) -> usize {
+    self.patterns.values().map(PatternSet::pattern_count).sum()
   }
 
   /// 检查是否包含指定扩展名
   pub fn contains_extension(&self, ext: &str) -> bool {
-    self.patterns.contains_key(ext)
+    self.patterns.contains_key(&ext.to_lowercase())
   }
 
   /// 获取指定扩展名的模式集合
   pub fn get_pattern_set(&self, ext: &str) -> Option<&PatternSet> {
-    self.patterns.get(ext)
+    self.patterns.get(&ext.to_lowercase())
   }
 }
 
 #[derive(Debug, Deserialize)]
 pub struct RawPatternConfig {
+  /// 翻译标记前缀，未设置时使用默认值 "(T) "
+  #[serde(default)]
+  pub prefix: Option<String>,
+  /// 同样视为已处理的其他标记前缀（如表示待校对的 "(R) "），生成时不再添加标记
+  #[serde(default)]
+  pub markers: Vec<String>,
+  /// 按相对路径设置翻译标记前缀，第一个匹配的 glob 生效，优先于后缀名的设置
+  #[serde(default)]
+  pub path_prefixes: Vec<RawPathPrefix>,
+  /// 路径经过纯数字对象键（如 "0"）时的处理方式："allow"、"warn" 或 "skip"
+  #[serde(default)]
+  pub numeric_keys: Option<NumericKeyPolicy>,
   #[serde(flatten)]
-  pub patterns: HashMap<String, Vec<String>>,
+  pub patterns: HashMap<String, RawPatternSet>,
+}
+
+/// 路径经过纯数字对象键时的处理方式
+/// 这类键容易被 patch 应用方误认为数组下标
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NumericKeyPolicy {
+  /// 正常生成，不提示
+  Allow,
+  /// 正常生成，并输出警告
+  #[default]
+  Warn,
+  /// 不生成该路径的操作
+  Skip,
+}
+
+/// 按相对路径 glob 设置的翻译标记前缀
+#[derive(Debug, Deserialize)]
+pub struct RawPathPrefix {
+  pub glob: String,
+  pub prefix: String,
+}
+
+/// 单个后缀名的原始模式配置
+/// 可以是正则数组，也可以是带有 include/exclude 的对象
+#[derive(Debug)]
+pub enum RawPatternSet {
+  List(Vec<String>),
+  Rules(RawPatternRules),
+}
+
+impl<'de> Deserialize<'de> for RawPatternSet {
+  // 不使用 untagged，以便对象中的未知字段（如拼错的 "exlude"）报告具体的字段名
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    let value = Value::deserialize(deserializer)?;
+    let raw_pattern_set = match value {
+      Value::Array(_) => serde_json::from_value(value).map(RawPatternSet::List),
+      _ => serde_json::from_value(value).map(RawPatternSet::Rules),
+    };
+    raw_pattern_set.map_err(D::Error::custom)
+  }
+}
+
+/// 对象形式的模式配置，不允许未知字段
+#[derive(Debug, Deserialize)]
+#[serde(
+  deny_unknown_fields,
+  expecting = "an array of patterns or an object with include/exclude"
+)]
+pub struct RawPatternRules {
+  pub include: Vec<String>,
+  #[serde(default)]
+  pub exclude: Vec<String>,
+  /// 正则是否忽略大小写
+  #[serde(default)]
+  pub case_insensitive: bool,
+  /// 字符串与其键相同时不生成操作，数组元素与数组的键比较，patch 文件中的值同样适用
+  #[serde(default)]
+  pub skip_if_equals_key: bool,
+  /// 该后缀名的翻译标记前缀，优先于顶层的 prefix，不能与 template 同时使用
+  #[serde(default)]
+  pub prefix: Option<String>,
+  /// 翻译标记模板，"{}" 处为原始字符串，未设置时使用 prefix
+  #[serde(default)]
+  pub template: Option<String>,
+  /// 该后缀名额外识别的其他标记前缀，与全局的 markers 合并
+  #[serde(default)]
+  pub markers: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const POINTERS: [&str; 10] = [
+    "/title",
+    "/Title",
+    "/titles",
+    "/description",
+    "/items/0/name",
+    "/items/0/description",
+    "/ITEMS/1/name",
+    "/effects/name",
+    "",
+    "/",
+  ];
+
+  /// 逐个正则匹配，作为分组匹配的参照
+  fn naive_first_match(
+    patterns: &[&str],
+    exclude: &[&str],
+    case_insensitive: bool,
+    json_pointer: &str,
+  ) -> Option<usize> {
+    let build = |pattern: &&str| {
+      regex::RegexBuilder::new(pattern)
+        .case_insensitive(case_insensitive)
+        .build()
+        .unwrap()
+    };
+    if exclude
+      .iter()
+      .map(build)
+      .any(|regex| regex.is_match(json_pointer))
+    {
+      return None;
+    }
+    patterns
+      .iter()
+      .map(build)
+      .position(|regex| regex.is_match(json_pointer))
+  }
+
+  #[test]
+  fn grouped_matching_agrees_with_a_naive_scan() {
+    let patterns = [
+      "^/title$",
+      "^/items/[0-9]+/name$",
+      "/name$",
+      "^/items/",
+      "^/(effects|items)/.*name$",
+      "^/description",
+      "^/$",
+    ];
+    let exclude = ["^/items/1/"];
+    for case_insensitive in [false, true] {
+      let pattern_set = PatternSet::new(
+        patterns.iter().map(|x| x.to_string()).collect(),
+        exclude.iter().map(|x| x.to_string()).collect(),
+        case_insensitive,
+      )
+      .unwrap();
+      for json_pointer in POINTERS {
+        let expected = naive_first_match(&patterns, &exclude, case_insensitive, json_pointer);
+        assert_eq!(
+          pattern_set.explain(json_pointer).map(|(index, _)| index),
+          expected,
+          "{} (case_insensitive: {})",
+          json_pointer,
+          case_insensitive
+        );
+        assert_eq!(pattern_set.is_match(json_pointer), expected.is_some());
+      }
+    }
+  }
+
+  #[test]
+  fn rules_reject_unknown_fields() {
+    let error =
+      PatternConfig::from_json5_str(r#"{"codex": {"include": ["^/a$"], "exlude": ["^/b$"]}}"#)
+        .unwrap_err();
+    assert!(
+      format!("{:#}", error).contains("unknown field `exlude`"),
+      "{:#}",
+      error
+    );
+    assert!(
+      PatternConfig::from_json5_str(r#"{"codex": {"include": ["^/a$"], "exclude": ["^/b$"]}}"#)
+        .is_ok()
+    );
+  }
+
+  #[test]
+  fn marker_precedence() {
+    let config = PatternConfig::from_json5_str(
+      r#"{
+        "prefix": "(G) ",
+        "path_prefixes": [{"glob": "dialog/**", "prefix": "(D) "}],
+        "codex": {"include": ["^/title$"], "prefix": "(C) "},
+        "item": {"include": ["^/title$"], "template": "[{}]"},
+        "object": ["^/title$"]
+      }"#,
+    )
+    .unwrap();
+    assert_eq!(
+      config.resolve_prefix("object", Path::new("objects/a.object")),
+      "(G) "
+    );
+    assert_eq!(
+      config.resolve_prefix("codex", Path::new("codex/a.codex")),
+      "(C) "
+    );
+    assert_eq!(
+      config.resolve_prefix("codex", Path::new("dialog/a.codex")),
+      "(D) "
+    );
+    assert_eq!(
+      config
+        .resolve_marker("item", Path::new("items/a.item"))
+        .mark("x"),
+      "[x]"
+    );
+    let config = config.with_prefix_override("(O) ");
+    assert_eq!(
+      config.resolve_prefix("codex", Path::new("dialog/a.codex")),
+      "(O) "
+    );
+  }
+
+  #[test]
+  fn prefix_and_template_are_exclusive() {
+    assert!(
+      PatternConfig::from_json5_str(
+        r#"{"codex": {"include": ["^/a$"], "prefix": "(T) ", "template": "{}"}}"#
+      )
+      .is_err()
+    );
+  }
+
+  #[test]
+  fn numeric_keys_option() {
+    let config =
+      PatternConfig::from_json5_str(r#"{"numeric_keys": "skip", "codex": ["^/a$"]}"#).unwrap();
+    assert_eq!(config.numeric_key_policy(), Some(NumericKeyPolicy::Skip));
+    assert_eq!(config.extensions().collect::<Vec<_>>(), ["codex"]);
+    let config = PatternConfig::from_json5_str(r#"{"codex": ["^/a$"]}"#).unwrap();
+    assert_eq!(config.numeric_key_policy(), None);
+    assert!(
+      PatternConfig::from_json5_str(r#"{"numeric_keys": "skp", "codex": ["^/a$"]}"#).is_err()
+    );
+  }
+
+  #[test]
+  fn counts() {
+    let config = PatternConfig::from_json5_str(
+      r#"{"codex": ["^/a$", "^/b$"], "item": {"include": ["^/c$"], "exclude": ["^/d$"]}}"#,
+    )
+    .unwrap();
+    assert_eq!(config.extensions().collect::<Vec<_>>(), ["codex", "item"]);
+    assert_eq!(config.pattern_count(), 4);
+  }
 }
@@ -0,0 +1,61 @@
+/// 可设定种子的伪随机数生成器（SplitMix64），同一种子总是产生相同序列
+pub struct SplitMix64 {
+  state: u64,
+}
+
+impl SplitMix64 {
+  pub fn new(seed: u64) -> Self {
+    Self { state: seed }
+  }
+
+  pub fn next_u64(&mut self) -> u64 {
+    self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = self.state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+  }
+
+  /// 生成 [0, upper) 范围内的随机数
+  pub fn gen_below(&mut self, upper: usize) -> usize {
+    (self.next_u64() % upper as u64) as usize
+  }
+}
+
+/// 从 items 中随机抽取最多 n 个元素（部分 Fisher-Yates 洗牌）
+pub fn sample<T>(mut items: Vec<T>, n: usize, seed: u64) -> Vec<T> {
+  let mut rng = SplitMix64::new(seed);
+  let n = n.min(items.len());
+  for i in 0..n {
+    let j = i + rng.gen_below(items.len() - i);
+    items.swap(i, j);
+  }
+  items.truncate(n);
+  items
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn fixed_seed_gives_a_deterministic_sample() {
+    let items: Vec<usize> = (0..100).collect();
+    let first = sample(items.clone(), 10, 42);
+    assert_eq!(first, sample(items.clone(), 10, 42));
+    assert_ne!(first, sample(items.clone(), 10, 43));
+    // 抽取的元素互不相同
+    let mut unique = first.clone();
+    unique.sort();
+    unique.dedup();
+    assert_eq!(unique.len(), 10);
+  }
+
+  #[test]
+  fn sample_larger_than_input_returns_everything() {
+    let mut sampled = sample(vec![1, 2, 3], 10, 7);
+    sampled.sort();
+    assert_eq!(sampled, [1, 2, 3]);
+    assert!(sample(Vec::<u8>::new(), 3, 7).is_empty());
+  }
+}
@@ -0,0 +1,251 @@
+/// 按行比较的编辑操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit {
+  /// 两边相同的行
+  Equal,
+  /// 只在旧文本中的行
+  Delete,
+  /// 只在新文本中的行
+  Insert,
+}
+
+/// 生成两段文本的统一格式（unified）diff，每个改动前后保留 context 行上下文
+/// 两段文本相同时返回空字符串
+pub fn unified_diff(
+  old_text: &str,
+  new_text: &str,
+  old_label: &str,
+  new_label: &str,
+  context: usize,
+) -> String {
+  let old_lines: Vec<&str> = old_text.lines().collect();
+  let new_lines: Vec<&str> = new_text.lines().collect();
+  let edits = diff_lines(&old_lines, &new_lines);
+  if edits.iter().all(|(edit, _)| *edit == Edit::Equal) {
+    return String::new();
+  }
+
+  // 每个编辑操作之前已经过的旧/新行数
+  let mut positions = Vec::with_capacity(edits.len() + 1);
+  let (mut old_pos, mut new_pos) = (0, 0);
+  for (edit, _) in &edits {
+    positions.push((old_pos, new_pos));
+    match edit {
+      Edit::Equal => {
+        old_pos += 1;
+        new_pos += 1;
+      }
+      Edit::Delete => old_pos += 1,
+      Edit::Insert => new_pos += 1,
+    }
+  }
+  positions.push((old_pos, new_pos));
+
+  // 把间隔不超过 2 * context 行的改动合并为同一个 hunk
+  let changes: Vec<usize> = edits
+    .iter()
+    .enumerate()
+    .filter(|(_, (edit, _))| *edit != Edit::Equal)
+    .map(|(index, _)| index)
+    .collect();
+  let mut hunks: Vec<(usize, usize)> = Vec::new();
+  for &index in &changes {
+    let start = index.saturating_sub(context);
+    let end = (index + context + 1).min(edits.len());
+    match hunks.last_mut() {
+      Some(last) if start <= last.1 => last.1 = end,
+      _ => hunks.push((start, end)),
+    }
+  }
+
+  let mut diff = format!("--- {}\n+++ {}\n", old_label, new_label);
+  for (start, end) in hunks {
+    let (old_start, new_start) = positions[start];
+    let old_len = positions[end].0 - old_start;
+    let new_len = positions[end].1 - new_start;
+    diff.push_str(&format!(
+      "@@ -{} +{} @@\n",
+      hunk_range(old_start, old_len),
+      hunk_range(new_start, new_len)
+    ));
+    for (edit, line) in &edits[start..end] {
+      let mark = match edit {
+        Edit::Equal => ' ',
+        Edit::Delete => '-',
+        Edit::Insert => '+',
+      };
+      diff.push(mark);
+      diff.push_str(line);
+      diff.push('\n');
+    }
+  }
+  diff
+}
+
+/// hunk 头中的行范围，长度为 0 时起始行为改动前一行
+fn hunk_range(start: usize, len: usize) -> String {
+  let start = if len == 0 { start } else { start + 1 };
+  if len == 1 {
+    start.to_string()
+  } else {
+    format!("{},{}", start, len)
+  }
+}
+
+/// 使用 Myers 算法按行比较，返回最短的编辑序列
+/// 先去掉相同的开头和结尾，只对中间不同的部分运行算法
+fn diff_lines<'a>(old_lines: &[&'a str], new_lines: &[&'a str]) -> Vec<(Edit, &'a str)> {
+  let prefix = old_lines
+    .iter()
+    .zip(new_lines)
+    .take_while(|(a, b)| a == b)
+    .count();
+  let suffix = old_lines[prefix..]
+    .iter()
+    .rev()
+    .zip(new_lines[prefix..].iter().rev())
+    .take_while(|(a, b)| a == b)
+    .count();
+  let old_middle = &old_lines[prefix..old_lines.len() - suffix];
+  let new_middle = &new_lines[prefix..new_lines.len() - suffix];
+
+  let mut edits: Vec<(Edit, &str)> = old_lines[..prefix]
+    .iter()
+    .map(|line| (Edit::Equal, *line))
+    .collect();
+  edits.extend(myers(old_middle, new_middle));
+  edits.extend(
+    old_lines[old_lines.len() - suffix..]
+      .iter()
+      .map(|line| (Edit::Equal, *line)),
+  );
+  edits
+}
+
+/// Myers 差分算法，记录每一步的对角线位置后回溯得到编辑序列
+fn myers<'a>(old_lines: &[&'a str], new_lines: &[&'a str]) -> Vec<(Edit, &'a str)> {
+  let (n, m) = (old_lines.len() as isize, new_lines.len() as isize);
+  let max = n + m;
+  let offset = max + 1;
+  let mut v = vec![0isize; 2 * max as usize + 3];
+  // 第 d 步开始前的对角线 -d..=d 的位置
+  let mut trace: Vec<Vec<isize>> = Vec::new();
+
+  'search: for d in 0..=max {
+    trace.push(v[(offset - d) as usize..=(offset + d) as usize].to_vec());
+    for k in (-d..=d).step_by(2) {
+      let index = (offset + k) as usize;
+      let mut x = if k == -d || (k != d && v[index - 1] < v[index + 1]) {
+        v[index + 1]
+      } else {
+        v[index - 1] + 1
+      };
+      let mut y = x - k;
+      while x < n && y < m && old_lines[x as usize] == new_lines[y as usize] {
+        x += 1;
+        y += 1;
+      }
+      v[index] = x;
+      if x >= n && y >= m {
+        break 'search;
+      }
+    }
+  }
+
+  // 从终点回溯
+  let mut edits = Vec::new();
+  let (mut x, mut y) = (n, m);
+  for (d, row) in trace.iter().enumerate().rev() {
+    let d = d as isize;
+    // row 中下标 0 对应对角线 -d
+    let get = |k: isize| row[(k + d) as usize];
+    let k = x - y;
+    let prev_k = if k == -d || (k != d && get(k - 1) < get(k + 1)) {
+      k + 1
+    } else {
+      k - 1
+    };
+    let (prev_x, prev_y) = if d == 0 {
+      (0, 0)
+    } else {
+      (get(prev_k), get(prev_k) - prev_k)
+    };
+    while x > prev_x && y > prev_y {
+      edits.push((Edit::Equal, old_lines[(x - 1) as usize]));
+      x -= 1;
+      y -= 1;
+    }
+    if d > 0 {
+      if x == prev_x {
+        edits.push((Edit::Insert, new_lines[(y - 1) as usize]));
+      } else {
+        edits.push((Edit::Delete, old_lines[(x - 1) as usize]));
+      }
+    }
+    x = prev_x;
+    y = prev_y;
+  }
+  edits.reverse();
+  edits
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn identical_texts_have_no_diff() {
+    assert_eq!(unified_diff("a\nb\n", "a\nb\n", "old", "new", 3), "");
+  }
+
+  #[test]
+  fn diff_contains_the_changed_line() {
+    let old = "[\n  {\n    \"value\": \"(T) Old\"\n  }\n]\n";
+    let new = "[\n  {\n    \"value\": \"(T) New\"\n  }\n]\n";
+    assert_eq!(
+      unified_diff(old, new, "a.patch", "a.patch (new)", 1),
+      "--- a.patch\n+++ a.patch (new)\n@@ -2,3 +2,3 @@\n   {\n-    \"value\": \"(T) Old\"\n+    \"value\": \"(T) New\"\n   }\n"
+    );
+  }
+
+  #[test]
+  fn distant_changes_get_separate_hunks() {
+    let old: String = (1..=20).map(|i| format!("{}\n", i)).collect();
+    let new = old
+      .replacen("2\n", "two\n", 1)
+      .replace("19\n", "nineteen\n");
+    let diff = unified_diff(&old, &new, "old", "new", 2);
+    assert_eq!(diff.matches("@@ -").count(), 2, "{}", diff);
+    assert!(diff.contains("-19\n+nineteen\n"), "{}", diff);
+  }
+
+  #[test]
+  fn insertions_and_deletions() {
+    assert_eq!(
+      unified_diff("", "a\n", "old", "new", 3),
+      "--- old\n+++ new\n@@ -0,0 +1 @@\n+a\n"
+    );
+    assert_eq!(
+      unified_diff("a\nb\nc\n", "a\nc\n", "old", "new", 0),
+      "--- old\n+++ new\n@@ -2 +1,0 @@\n-b\n"
+    );
+  }
+
+  #[test]
+  fn myers_finds_a_shortest_edit_script() {
+    let old = ["a", "b", "c", "a", "b", "b", "a"];
+    let new = ["c", "b", "a", "b", "a", "c"];
+    let edits = myers(&old, &new);
+    let changes = edits
+      .iter()
+      .filter(|(edit, _)| *edit != Edit::Equal)
+      .count();
+    assert_eq!(changes, 5);
+    let rebuilt: Vec<&str> = edits
+      .iter()
+      .filter(|(edit, _)| *edit != Edit::Delete)
+      .map(|(_, line)| *line)
+      .collect();
+    assert_eq!(rebuilt, new);
+  }
+}
@@ -0,0 +1,171 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// 解析 RFC 3339 时间戳，如 "2024-05-01T12:30:00Z" 或 "2024-05-01T12:30:00.5+08:00"
+pub fn parse_rfc3339(text: &str) -> Option<SystemTime> {
+  let text = text.trim();
+  let (date, rest) = text.split_at_checked(10)?;
+  let rest = rest.strip_prefix(['T', 't', ' '])?;
+
+  // 日期部分
+  let mut date_parts = date.split('-');
+  let year: i64 = parse_digits(date_parts.next()?, 4)?;
+  let month: i64 = parse_digits(date_parts.next()?, 2)?;
+  let day: i64 = parse_digits(date_parts.next()?, 2)?;
+  if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+    return None;
+  }
+
+  // 时间部分
+  let (time, rest) = rest.split_at_checked(8)?;
+  let mut time_parts = time.split(':');
+  let hour: i64 = parse_digits(time_parts.next()?, 2)?;
+  let minute: i64 = parse_digits(time_parts.next()?, 2)?;
+  let second: i64 = parse_digits(time_parts.next()?, 2)?;
+  if hour > 23 || minute > 59 || second > 60 {
+    return None;
+  }
+
+  // 可选的小数秒
+  let (nanos, offset) = match rest.strip_prefix('.') {
+    Some(rest) => {
+      let end = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+      if end == 0 {
+        return None;
+      }
+      let fraction = &rest[..end.min(9)];
+      let nanos = fraction.parse::<u32>().ok()? * 10u32.pow(9 - fraction.len() as u32);
+      (nanos, &rest[end..])
+    }
+    None => (0, rest),
+  };
+
+  // 时区偏移
+  let offset_seconds = match offset {
+    "Z" | "z" => 0,
+    _ => {
+      let sign = match offset.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+      };
+      let (offset_hour, offset_minute) = offset[1..].split_once(':')?;
+      sign
+        * (parse_digits::<i64>(offset_hour, 2)? * 3600
+          + parse_digits::<i64>(offset_minute, 2)? * 60)
+    }
+  };
+
+  let seconds =
+    days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 + second - offset_seconds;
+  if seconds >= 0 {
+    UNIX_EPOCH.checked_add(Duration::new(seconds as u64, nanos))
+  } else {
+    UNIX_EPOCH
+      .checked_sub(Duration::from_secs(seconds.unsigned_abs()))?
+      .checked_add(Duration::from_nanos(nanos as u64))
+  }
+}
+
+/// 格式化为 UTC 的 RFC 3339 时间戳（精确到秒）
+pub fn format_rfc3339(time: SystemTime) -> String {
+  let seconds = match time.duration_since(UNIX_EPOCH) {
+    Ok(duration) => duration.as_secs() as i64,
+    Err(e) => -(e.duration().as_secs_f64().ceil() as i64),
+  };
+  let (days, day_seconds) = (seconds.div_euclid(86400), seconds.rem_euclid(86400));
+  let (year, month, day) = civil_from_days(days);
+  format!(
+    "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+    year,
+    month,
+    day,
+    day_seconds / 3600,
+    day_seconds % 3600 / 60,
+    day_seconds % 60
+  )
+}
+
+/// 解析固定位数的数字
+fn parse_digits<T: std::str::FromStr>(text: &str, len: usize) -> Option<T> {
+  if text.len() != len || !text.bytes().all(|b| b.is_ascii_digit()) {
+    return None;
+  }
+  text.parse().ok()
+}
+
+/// 公历日期转换为距 1970-01-01 的天数
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+  let year = if month <= 2 { year - 1 } else { year };
+  let era = year.div_euclid(400);
+  let year_of_era = year - era * 400;
+  let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+  let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+  era * 146097 + day_of_era - 719468
+}
+
+/// 距 1970-01-01 的天数转换为公历日期
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+  let days = days + 719468;
+  let era = days.div_euclid(146097);
+  let day_of_era = days - era * 146097;
+  let year_of_era =
+    (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+  let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+  let month_index = (5 * day_of_year + 2) / 153;
+  let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+  let month = if month_index < 10 {
+    month_index + 3
+  } else {
+    month_index - 9
+  };
+  let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+  (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parses_offsets_and_fractions() {
+    let expected = UNIX_EPOCH + Duration::from_secs(1714566600);
+    assert_eq!(parse_rfc3339("2024-05-01T12:30:00Z"), Some(expected));
+    assert_eq!(parse_rfc3339("2024-05-01T20:30:00+08:00"), Some(expected));
+    assert_eq!(parse_rfc3339("2024-05-01 12:30:00z"), Some(expected));
+    assert_eq!(
+      parse_rfc3339("2024-05-01T12:30:00.5Z"),
+      Some(expected + Duration::from_millis(500))
+    );
+    assert_eq!(
+      parse_rfc3339("1969-12-31T23:59:59Z"),
+      UNIX_EPOCH.checked_sub(Duration::from_secs(1))
+    );
+  }
+
+  #[test]
+  fn rejects_invalid_timestamps() {
+    for text in [
+      "2024-05-01",
+      "2024-13-01T00:00:00Z",
+      "2024-05-01T24:00:00Z",
+      "2024-05-01T12:30:00",
+      "2024-05-01T12:30:00.Z",
+      "2024-5-01T12:30:00Z",
+    ] {
+      assert_eq!(parse_rfc3339(text), None, "{}", text);
+    }
+  }
+
+  #[test]
+  fn format_round_trips() {
+    for text in [
+      "1970-01-01T00:00:00Z",
+      "2000-02-29T23:59:59Z",
+      "2024-05-01T12:30:00Z",
+    ] {
+      assert_eq!(format_rfc3339(parse_rfc3339(text).unwrap()), text);
+    }
+  }
+}
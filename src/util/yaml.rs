@@ -0,0 +1,186 @@
+use serde_json::Value;
+
+/// 把 JSON 值序列化为块格式的 YAML
+/// 字符串和键统一使用双引号（JSON 字符串的转义在 YAML 双引号字符串中同样有效），
+/// 空数组和空对象使用流格式 "[]" 和 "{}"
+pub fn to_string(value: &Value) -> String {
+  let mut out = String::new();
+  if is_inline(value) {
+    out.push_str(&scalar_to_string(value));
+    out.push('\n');
+  } else {
+    write_block(value, 0, false, &mut out);
+  }
+  out
+}
+
+/// 标量、空数组和空对象可以写在同一行
+fn is_inline(value: &Value) -> bool {
+  match value {
+    Value::Array(array_value) => array_value.is_empty(),
+    Value::Object(object_value) => object_value.is_empty(),
+    _ => true,
+  }
+}
+
+fn scalar_to_string(value: &Value) -> String {
+  match value {
+    Value::Array(_) => "[]".to_string(),
+    Value::Object(_) => "{}".to_string(),
+    // 字符串、数字、布尔值和 null 的 JSON 写法都是合法的 YAML
+    _ => value.to_string(),
+  }
+}
+
+/// 简单的标识符键不加引号，其余的键（包括 YAML 会当作布尔值或 null 的词）加双引号
+fn key_to_string(key: &str) -> String {
+  let is_identifier = key
+    .chars()
+    .next()
+    .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+    && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+  let is_reserved = matches!(
+    key.to_ascii_lowercase().as_str(),
+    "true" | "false" | "null" | "yes" | "no" | "on" | "off" | "y" | "n"
+  );
+  if is_identifier && !is_reserved {
+    key.to_string()
+  } else {
+    Value::String(key.to_string()).to_string()
+  }
+}
+
+/// 写入非空的数组或对象
+/// inline_first 为 true 时第一项紧跟在当前行（如 "- " 之后），不再缩进
+fn write_block(value: &Value, indent: usize, inline_first: bool, out: &mut String) {
+  let padding = " ".repeat(indent);
+  match value {
+    Value::Array(array_value) => {
+      for (index, item) in array_value.iter().enumerate() {
+        if index > 0 || !inline_first {
+          out.push_str(&padding);
+        }
+        out.push_str("- ");
+        if is_inline(item) {
+          out.push_str(&scalar_to_string(item));
+          out.push('\n');
+        } else {
+          write_block(item, indent + 2, true, out);
+        }
+      }
+    }
+    Value::Object(object_value) => {
+      for (index, (key, item)) in object_value.iter().enumerate() {
+        if index > 0 || !inline_first {
+          out.push_str(&padding);
+        }
+        out.push_str(&key_to_string(key));
+        out.push(':');
+        if is_inline(item) {
+          out.push(' ');
+          out.push_str(&scalar_to_string(item));
+          out.push('\n');
+        } else {
+          out.push('\n');
+          write_block(item, indent + 2, false, out);
+        }
+      }
+    }
+    _ => {
+      out.push_str(&scalar_to_string(value));
+      out.push('\n');
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  /// 读回 to_string 输出的 YAML 子集：块格式的数组和对象，标量均为 JSON 写法
+  fn parse(yaml: &str) -> Value {
+    let mut lines: Vec<String> = yaml.lines().map(str::to_string).collect();
+    let mut pos = 0;
+    let value = parse_node(&mut lines, &mut pos, 0);
+    assert_eq!(pos, lines.len(), "trailing lines in {}", yaml);
+    value
+  }
+
+  fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+  }
+
+  fn parse_node(lines: &mut [String], pos: &mut usize, indent: usize) -> Value {
+    let first = lines[*pos][indent..].to_string();
+    if let Ok(scalar) = serde_json::from_str(&first) {
+      *pos += 1;
+      return scalar;
+    }
+    if first.starts_with("- ") {
+      let mut array_value = Vec::new();
+      while *pos < lines.len()
+        && indent_of(&lines[*pos]) == indent
+        && lines[*pos][indent..].starts_with("- ")
+      {
+        // 把 "- " 换成空格，第一项就与后续各项缩进相同
+        lines[*pos].replace_range(indent..indent + 2, "  ");
+        array_value.push(parse_node(lines, pos, indent + 2));
+      }
+      return Value::Array(array_value);
+    }
+    let mut object_value = serde_json::Map::new();
+    while *pos < lines.len() && indent_of(&lines[*pos]) == indent {
+      let line = lines[*pos][indent..].to_string();
+      let (key, rest) = if line.starts_with('"') {
+        let mut stream = serde_json::Deserializer::from_str(&line).into_iter::<String>();
+        let key = stream.next().unwrap().unwrap();
+        (key, line[stream.byte_offset()..].to_string())
+      } else {
+        let colon = line.find(':').unwrap();
+        (line[..colon].to_string(), line[colon..].to_string())
+      };
+      let value = match rest.strip_prefix(": ") {
+        Some(scalar) => {
+          *pos += 1;
+          serde_json::from_str(scalar).unwrap()
+        }
+        None => {
+          assert_eq!(rest, ":");
+          *pos += 1;
+          parse_node(lines, pos, indent + 2)
+        }
+      };
+      object_value.insert(key, value);
+    }
+    Value::Object(object_value)
+  }
+
+  #[test]
+  fn patches_round_trip() {
+    let values = [
+      json!([
+        {"op": "replace", "path": "/title", "value": "说 \"你好\"\n第二行: #1"},
+        {"op": "replace", "path": "/pages", "value": ["- a", "b: c", [], {}]},
+        {"op": "add", "path": "/nested", "value": {"true": null, "0": 1.5, "a b": [[1, -2], {"k": false}]}},
+        {"op": "remove", "path": "/unused"}
+      ]),
+      json!([
+        [{"op": "test", "path": "/a", "value": "x"}, {"op": "replace", "path": "/a", "value": "y"}],
+        [{"op": "replace", "path": "/b", "value": "yes"}]
+      ]),
+      json!([]),
+      json!("scalar"),
+    ];
+    for value in values {
+      let yaml = to_string(&value);
+      assert_eq!(parse(&yaml), value, "{}", yaml);
+    }
+  }
+
+  #[test]
+  fn reserved_and_unusual_keys_are_quoted() {
+    let yaml = to_string(&json!({"op": 1, "yes": 2, "a-b": 3, "": 4}));
+    assert_eq!(yaml, "op: 1\n\"yes\": 2\n\"a-b\": 3\n\"\": 4\n");
+  }
+}
@@ -0,0 +1,602 @@
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result, bail};
+
+/// ZIP 压缩包中的文件条目
+#[derive(Debug)]
+pub struct ZipEntry {
+  /// 压缩包内的路径，分隔符为 '/'
+  pub name: String,
+  /// 压缩方式：0 为不压缩，8 为 deflate
+  method: u16,
+  /// 通用标志位
+  flags: u16,
+  crc32: u32,
+  compressed_size: usize,
+  uncompressed_size: usize,
+  /// 本地文件头的偏移
+  local_header_offset: usize,
+}
+
+impl ZipEntry {
+  /// 是否为目录条目
+  pub fn is_dir(&self) -> bool {
+    self.name.ends_with('/')
+  }
+}
+
+/// 读入内存的 ZIP 压缩包，只支持不压缩和 deflate 两种压缩方式，不支持 ZIP64 和加密
+pub struct ZipArchive {
+  data: Vec<u8>,
+  entries: Vec<ZipEntry>,
+}
+
+impl ZipArchive {
+  /// 读取压缩包并解析中央目录
+  pub fn open(path: &Path) -> Result<Self> {
+    let data = fs::read(path).context("Failed to read archive")?;
+    let entries = read_central_directory(&data)?;
+    Ok(Self { data, entries })
+  }
+
+  /// 所有条目（包括目录）
+  pub fn entries(&self) -> &[ZipEntry] {
+    &self.entries
+  }
+
+  /// 解压单个条目并校验 CRC32
+  /// 解压后的大小不能超过中央目录中记录的大小，也不能超过 MAX_ENTRY_SIZE
+  pub fn read(&self, entry: &ZipEntry) -> Result<Vec<u8>> {
+    if entry.flags & 1 != 0 {
+      bail!("{}: encrypted entries are not supported", entry.name);
+    }
+    if entry.uncompressed_size > MAX_ENTRY_SIZE {
+      bail!(
+        "{}: entry is too large ({} bytes, limit {} bytes)",
+        entry.name,
+        entry.uncompressed_size,
+        MAX_ENTRY_SIZE
+      );
+    }
+    let header = entry.local_header_offset;
+    if read_u32(&self.data, header)? != LOCAL_HEADER_SIGNATURE {
+      bail!("{}: bad local file header", entry.name);
+    }
+    let start = header
+      + 30
+      + read_u16(&self.data, header + 26)? as usize
+      + read_u16(&self.data, header + 28)? as usize;
+    let compressed = self
+      .data
+      .get(start..start + entry.compressed_size)
+      .context(format!("{}: entry data is truncated", entry.name))?;
+    let contents = match entry.method {
+      0 => compressed.to_vec(),
+      8 => inflate(compressed, entry.uncompressed_size)
+        .context(format!("{}: invalid deflate data", entry.name))?,
+      method => bail!("{}: unsupported compression method {}", entry.name, method),
+    };
+    if contents.len() != entry.uncompressed_size || crc32(&contents) != entry.crc32 {
+      bail!("{}: checksum mismatch", entry.name);
+    }
+    Ok(contents)
+  }
+}
+
+/// 单个条目解压后的最大大小
+const MAX_ENTRY_SIZE: usize = 256 * 1024 * 1024;
+
+const LOCAL_HEADER_SIGNATURE: u32 = 0x04034b50;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x02014b50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x06054b50;
+
+/// 从文件末尾查找中央目录结束记录，解析中央目录中的所有条目
+fn read_central_directory(data: &[u8]) -> Result<Vec<ZipEntry>> {
+  // 结束记录固定部分为 22 字节，其后最多有 65535 字节的注释
+  let last = data.len().checked_sub(22).context("Not a ZIP archive")?;
+  let end = (last.saturating_sub(u16::MAX as usize)..=last)
+    .rev()
+    .find(|&offset| read_u32(data, offset).ok() == Some(END_OF_CENTRAL_DIRECTORY_SIGNATURE))
+    .context("Not a ZIP archive: end of central directory not found")?;
+  let entry_count = read_u16(data, end + 10)?;
+  let directory_offset = read_u32(data, end + 16)?;
+  if entry_count == u16::MAX || directory_offset == u32::MAX {
+    bail!("ZIP64 archives are not supported");
+  }
+
+  let mut entries = Vec::with_capacity(entry_count as usize);
+  let mut offset = directory_offset as usize;
+  for _ in 0..entry_count {
+    if read_u32(data, offset)? != CENTRAL_HEADER_SIGNATURE {
+      bail!("Bad central directory entry at offset {}", offset);
+    }
+    let flags = read_u16(data, offset + 8)?;
+    let name_len = read_u16(data, offset + 28)? as usize;
+    let extra_len = read_u16(data, offset + 30)? as usize;
+    let comment_len = read_u16(data, offset + 32)? as usize;
+    let name_bytes = data
+      .get(offset + 46..offset + 46 + name_len)
+      .context("Central directory is truncated")?;
+    let compressed_size = read_u32(data, offset + 20)?;
+    let uncompressed_size = read_u32(data, offset + 24)?;
+    let local_header_offset = read_u32(data, offset + 42)?;
+    if [compressed_size, uncompressed_size, local_header_offset].contains(&u32::MAX) {
+      bail!("ZIP64 archives are not supported");
+    }
+    entries.push(ZipEntry {
+      // 未设置 UTF-8 标志位的文件名按 UTF-8 尝试解码
+      name: String::from_utf8_lossy(name_bytes).replace('\\', "/"),
+      method: read_u16(data, offset + 10)?,
+      flags,
+      crc32: read_u32(data, offset + 16)?,
+      compressed_size: compressed_size as usize,
+      uncompressed_size: uncompressed_size as usize,
+      local_header_offset: local_header_offset as usize,
+    });
+    offset += 46 + name_len + extra_len + comment_len;
+  }
+  Ok(entries)
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16> {
+  let bytes = data
+    .get(offset..offset + 2)
+    .context("Unexpected end of archive")?;
+  Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32> {
+  let bytes = data
+    .get(offset..offset + 4)
+    .context("Unexpected end of archive")?;
+  Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// CRC32 查找表（多项式 0xEDB88320）
+const CRC32_TABLE: [u32; 256] = {
+  let mut table = [0u32; 256];
+  let mut i = 0;
+  while i < 256 {
+    let mut crc = i as u32;
+    let mut bit = 0;
+    while bit < 8 {
+      crc = if crc & 1 != 0 {
+        0xEDB88320 ^ (crc >> 1)
+      } else {
+        crc >> 1
+      };
+      bit += 1;
+    }
+    table[i] = crc;
+    i += 1;
+  }
+  table
+};
+
+fn crc32(data: &[u8]) -> u32 {
+  !data.iter().fold(!0u32, |crc, &byte| {
+    CRC32_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8)
+  })
+}
+
+/// 按 LSB 优先顺序读取比特
+struct BitReader<'a> {
+  data: &'a [u8],
+  pos: usize,
+  bit_buf: u32,
+  bit_count: u32,
+}
+
+impl BitReader<'_> {
+  fn bits(&mut self, count: u32) -> Result<u32> {
+    while self.bit_count < count {
+      let byte = *self.data.get(self.pos).context("Unexpected end of data")?;
+      self.bit_buf |= (byte as u32) << self.bit_count;
+      self.pos += 1;
+      self.bit_count += 8;
+    }
+    let value = self.bit_buf & ((1 << count) - 1);
+    self.bit_buf >>= count;
+    self.bit_count -= count;
+    Ok(value)
+  }
+
+  /// 丢弃当前字节中剩余的比特
+  fn align_to_byte(&mut self) {
+    self.bit_buf = 0;
+    self.bit_count = 0;
+  }
+}
+
+/// 范式 Huffman 编码表
+struct Huffman {
+  /// 每种码长的符号数
+  counts: [u16; 16],
+  /// 按码长、符号值排序的符号
+  symbols: Vec<u16>,
+}
+
+impl Huffman {
+  /// 由每个符号的码长构造，码长为 0 的符号不参与编码
+  fn new(lengths: &[u8]) -> Result<Self> {
+    let mut counts = [0u16; 16];
+    for &length in lengths {
+      counts[length as usize] += 1;
+    }
+    // 检查码长是否超额（允许不完整的编码）
+    let mut left: i32 = 1;
+    for &count in &counts[1..] {
+      left = (left << 1) - count as i32;
+      if left < 0 {
+        bail!("Over-subscribed Huffman code");
+      }
+    }
+    let mut offsets = [0u16; 16];
+    for length in 1..15 {
+      offsets[length + 1] = offsets[length] + counts[length];
+    }
+    let mut symbols = vec![0u16; lengths.len()];
+    for (symbol, &length) in lengths.iter().enumerate() {
+      if length != 0 {
+        symbols[offsets[length as usize] as usize] = symbol as u16;
+        offsets[length as usize] += 1;
+      }
+    }
+    Ok(Self { counts, symbols })
+  }
+
+  /// 逐比特解码一个符号
+  fn decode(&self, reader: &mut BitReader) -> Result<u16> {
+    let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+    for &count in &self.counts[1..] {
+      code |= reader.bits(1)? as i32;
+      let count = count as i32;
+      if code - first < count {
+        return Ok(self.symbols[(index + code - first) as usize]);
+      }
+      index += count;
+      first = (first + count) << 1;
+      code <<= 1;
+    }
+    bail!("Invalid Huffman code")
+  }
+}
+
+const LENGTH_BASE: [u16; 29] = [
+  3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+  163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+  0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+  1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049,
+  3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+  0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13,
+];
+/// 动态 Huffman 块中码长编码的码长顺序
+const CODE_LENGTH_ORDER: [usize; 19] = [
+  16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// 解压 deflate（RFC 1951）数据，输出超过 limit 字节时报错
+fn inflate(data: &[u8], limit: usize) -> Result<Vec<u8>> {
+  let mut reader = BitReader {
+    data,
+    pos: 0,
+    bit_buf: 0,
+    bit_count: 0,
+  };
+  let mut output = Vec::new();
+  loop {
+    let is_last = reader.bits(1)? == 1;
+    match reader.bits(2)? {
+      0 => inflate_stored(&mut reader, &mut output, limit)?,
+      1 => {
+        let (lengths, distances) = fixed_codes()?;
+        inflate_codes(&mut reader, &mut output, limit, &lengths, &distances)?;
+      }
+      2 => {
+        let (lengths, distances) = dynamic_codes(&mut reader)?;
+        inflate_codes(&mut reader, &mut output, limit, &lengths, &distances)?;
+      }
+      _ => bail!("Invalid block type"),
+    }
+    if is_last {
+      return Ok(output);
+    }
+  }
+}
+
+/// 不压缩的块
+fn inflate_stored(reader: &mut BitReader, output: &mut Vec<u8>, limit: usize) -> Result<()> {
+  reader.align_to_byte();
+  let header = reader
+    .data
+    .get(reader.pos..reader.pos + 4)
+    .context("Unexpected end of data")?;
+  let length = u16::from_le_bytes([header[0], header[1]]);
+  let complement = u16::from_le_bytes([header[2], header[3]]);
+  if length != !complement {
+    bail!("Stored block length does not match its complement");
+  }
+  let start = reader.pos + 4;
+  let block = reader
+    .data
+    .get(start..start + length as usize)
+    .context("Unexpected end of data")?;
+  check_output_size(output.len() + block.len(), limit)?;
+  output.extend_from_slice(block);
+  reader.pos = start + length as usize;
+  Ok(())
+}
+
+/// 固定 Huffman 编码
+fn fixed_codes() -> Result<(Huffman, Huffman)> {
+  let mut lengths = [0u8; 288];
+  lengths[..144].fill(8);
+  lengths[144..256].fill(9);
+  lengths[256..280].fill(7);
+  lengths[280..].fill(8);
+  Ok((Huffman::new(&lengths)?, Huffman::new(&[5; 30])?))
+}
+
+/// 读取动态 Huffman 编码
+fn dynamic_codes(reader: &mut BitReader) -> Result<(Huffman, Huffman)> {
+  let length_count = reader.bits(5)? as usize + 257;
+  let distance_count = reader.bits(5)? as usize + 1;
+  let code_length_count = reader.bits(4)? as usize + 4;
+  if length_count > 286 || distance_count > 30 {
+    bail!("Too many length or distance codes");
+  }
+
+  let mut code_lengths = [0u8; 19];
+  for &index in &CODE_LENGTH_ORDER[..code_length_count] {
+    code_lengths[index] = reader.bits(3)? as u8;
+  }
+  let code_length_code = Huffman::new(&code_lengths)?;
+
+  let mut lengths = Vec::with_capacity(length_count + distance_count);
+  while lengths.len() < length_count + distance_count {
+    let (length, repeat) = match code_length_code.decode(reader)? {
+      symbol @ 0..=15 => (symbol as u8, 1),
+      16 => (
+        *lengths.last().context("Repeat with no previous length")?,
+        3 + reader.bits(2)?,
+      ),
+      17 => (0, 3 + reader.bits(3)?),
+      _ => (0, 11 + reader.bits(7)?),
+    };
+    if lengths.len() + repeat as usize > length_count + distance_count {
+      bail!("Too many code lengths");
+    }
+    lengths.extend(std::iter::repeat_n(length, repeat as usize));
+  }
+  if lengths[256] == 0 {
+    bail!("Missing end-of-block code");
+  }
+  Ok((
+    Huffman::new(&lengths[..length_count])?,
+    Huffman::new(&lengths[length_count..])?,
+  ))
+}
+
+/// 按给定的 Huffman 编码解压一个块
+fn inflate_codes(
+  reader: &mut BitReader,
+  output: &mut Vec<u8>,
+  limit: usize,
+  lengths: &Huffman,
+  distances: &Huffman,
+) -> Result<()> {
+  loop {
+    let symbol = lengths.decode(reader)? as usize;
+    match symbol {
+      0..=255 => {
+        check_output_size(output.len() + 1, limit)?;
+        output.push(symbol as u8);
+      }
+      256 => return Ok(()),
+      _ => {
+        let index = symbol - 257;
+        if index >= LENGTH_BASE.len() {
+          bail!("Invalid length code");
+        }
+        let length =
+          LENGTH_BASE[index] as usize + reader.bits(LENGTH_EXTRA[index] as u32)? as usize;
+        let index = distances.decode(reader)? as usize;
+        if index >= DISTANCE_BASE.len() {
+          bail!("Invalid distance code");
+        }
+        let distance =
+          DISTANCE_BASE[index] as usize + reader.bits(DISTANCE_EXTRA[index] as u32)? as usize;
+        if distance > output.len() {
+          bail!("Distance too far back");
+        }
+        check_output_size(output.len() + length, limit)?;
+        let start = output.len() - distance;
+        for i in 0..length {
+          output.push(output[start + i]);
+        }
+      }
+    }
+  }
+}
+
+/// 解压后的数据不能超过声明的大小，防止压缩炸弹耗尽内存
+fn check_output_size(size: usize, limit: usize) -> Result<()> {
+  if size > limit {
+    bail!(
+      "Decompressed data exceeds the declared size of {} bytes",
+      limit
+    );
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+  use super::*;
+
+  /// 原始 deflate 数据，解压后为 {"title": "hello hello hello hello"}
+  const DEFLATED_JSON: [u8; 22] = [
+    0xab, 0x56, 0x2a, 0xc9, 0x2c, 0xc9, 0x49, 0x55, 0xb2, 0x52, 0x50, 0xca, 0x48, 0xcd, 0xc9, 0xc9,
+    0x57, 0xc0, 0x20, 0x95, 0x6a, 0x01,
+  ];
+  /// 原始 deflate 数据，解压后为 1000 个 'a'
+  const DEFLATED_AAA: [u8; 11] = [
+    0x4b, 0x4c, 0x1c, 0x05, 0xa3, 0x60, 0x14, 0x0c, 0x77, 0x00, 0x00,
+  ];
+
+  /// 测试用的条目：名称、压缩方式、压缩后的数据、解压后的数据、声明的解压后大小
+  pub(crate) struct TestEntry<'a> {
+    pub name: &'a str,
+    pub method: u16,
+    pub data: &'a [u8],
+    pub contents: &'a [u8],
+    pub declared_size: usize,
+  }
+
+  impl<'a> TestEntry<'a> {
+    /// 不压缩的条目
+    pub(crate) fn stored(name: &'a str, contents: &'a [u8]) -> Self {
+      Self {
+        name,
+        method: 0,
+        data: contents,
+        contents,
+        declared_size: contents.len(),
+      }
+    }
+  }
+
+  /// 在内存中构造压缩包
+  fn build_archive(entries: &[TestEntry]) -> ZipArchive {
+    let data = archive_bytes(entries);
+    let entries = read_central_directory(&data).unwrap();
+    ZipArchive { data, entries }
+  }
+
+  /// 生成压缩包文件的内容
+  pub(crate) fn archive_bytes(entries: &[TestEntry]) -> Vec<u8> {
+    let mut data = Vec::new();
+    let mut directory = Vec::new();
+    for entry in entries {
+      let offset = data.len() as u32;
+      let crc = crc32(entry.contents);
+      let sizes = [entry.data.len() as u32, entry.declared_size as u32];
+      data.extend(LOCAL_HEADER_SIGNATURE.to_le_bytes());
+      data.extend([20, 0, 0, 0]);
+      data.extend(entry.method.to_le_bytes());
+      data.extend([0; 4]);
+      data.extend(crc.to_le_bytes());
+      sizes
+        .iter()
+        .for_each(|size| data.extend(size.to_le_bytes()));
+      data.extend((entry.name.len() as u16).to_le_bytes());
+      data.extend([0, 0]);
+      data.extend(entry.name.as_bytes());
+      data.extend(entry.data);
+
+      directory.extend(CENTRAL_HEADER_SIGNATURE.to_le_bytes());
+      directory.extend([20, 0, 20, 0, 0, 0]);
+      directory.extend(entry.method.to_le_bytes());
+      directory.extend([0; 4]);
+      directory.extend(crc.to_le_bytes());
+      sizes
+        .iter()
+        .for_each(|size| directory.extend(size.to_le_bytes()));
+      directory.extend((entry.name.len() as u16).to_le_bytes());
+      directory.extend([0; 12]);
+      directory.extend(offset.to_le_bytes());
+      directory.extend(entry.name.as_bytes());
+    }
+    let directory_offset = data.len() as u32;
+    let directory_len = directory.len() as u32;
+    data.extend(directory);
+    data.extend(END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes());
+    data.extend([0; 4]);
+    data.extend((entries.len() as u16).to_le_bytes());
+    data.extend((entries.len() as u16).to_le_bytes());
+    data.extend(directory_len.to_le_bytes());
+    data.extend(directory_offset.to_le_bytes());
+    data.extend([0, 0]);
+    data
+  }
+
+  #[test]
+  fn reads_stored_and_deflated_entries() {
+    let json = br#"{"title": "hello hello hello hello"}"#;
+    let archive = build_archive(&[
+      TestEntry {
+        name: "codex/",
+        method: 0,
+        data: b"",
+        contents: b"",
+        declared_size: 0,
+      },
+      TestEntry::stored("codex/stored.codex", json),
+      TestEntry {
+        name: "codex\\deflated.codex",
+        method: 8,
+        data: &DEFLATED_JSON,
+        contents: json,
+        declared_size: json.len(),
+      },
+    ]);
+    let entries = archive.entries();
+    assert!(entries[0].is_dir());
+    assert_eq!(entries[2].name, "codex/deflated.codex");
+    assert_eq!(archive.read(&entries[1]).unwrap(), json);
+    assert_eq!(archive.read(&entries[2]).unwrap(), json);
+  }
+
+  #[test]
+  fn inflate_handles_back_references() {
+    assert_eq!(inflate(&DEFLATED_AAA, 1000).unwrap(), vec![b'a'; 1000]);
+  }
+
+  #[test]
+  fn output_is_capped_at_the_declared_size() {
+    let error = inflate(&DEFLATED_AAA, 999).unwrap_err();
+    assert!(
+      error.to_string().contains("exceeds the declared size"),
+      "{}",
+      error
+    );
+    let archive = build_archive(&[TestEntry {
+      name: "bomb.codex",
+      method: 8,
+      data: &DEFLATED_AAA,
+      contents: &[b'a'; 1000],
+      declared_size: 100,
+    }]);
+    let error = archive.read(&archive.entries()[0]).unwrap_err();
+    assert!(
+      format!("{:#}", error).contains("exceeds the declared size of 100 bytes"),
+      "{:#}",
+      error
+    );
+  }
+
+  #[test]
+  fn checksum_mismatch_is_reported() {
+    let archive = build_archive(&[TestEntry {
+      name: "a.codex",
+      method: 0,
+      data: b"abc",
+      contents: b"abd",
+      declared_size: 3,
+    }]);
+    let error = archive.read(&archive.entries()[0]).unwrap_err();
+    assert!(error.to_string().contains("checksum mismatch"), "{}", error);
+  }
+
+  #[test]
+  fn rejects_non_zip_data() {
+    assert!(read_central_directory(b"not a zip archive at all").is_err());
+    assert!(read_central_directory(b"").is_err());
+  }
+}
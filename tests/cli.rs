@@ -0,0 +1,39 @@
+use std::{fs, path::PathBuf, process::Command};
+
+/// 为测试创建空的临时目录
+fn temp_dir(name: &str) -> PathBuf {
+  let dir = std::env::temp_dir().join(format!("neki_lang-test-{}-{}", name, std::process::id()));
+  if dir.exists() {
+    fs::remove_dir_all(&dir).unwrap();
+  }
+  fs::create_dir_all(&dir).unwrap();
+  dir
+}
+
+#[test]
+fn quiet_gen_prints_nothing_to_stdout() {
+  let root = temp_dir("cli-quiet");
+  fs::create_dir_all(root.join("config")).unwrap();
+  fs::write(
+    root.join("config/regex_config.json"),
+    r#"{"codex": ["^/[a-z]+$"]}"#,
+  )
+  .unwrap();
+  fs::create_dir_all(root.join("mod/codex")).unwrap();
+  fs::write(root.join("mod/codex/a.codex"), r#"{"title": "A"}"#).unwrap();
+
+  let gen_output = |extra: &[&str]| {
+    let output = Command::new(env!("CARGO_BIN_EXE_neki_lang"))
+      .args(["gen", "-i", "mod", "-o", "out", "--config-dir", "config"])
+      .args(extra)
+      .current_dir(&root)
+      .output()
+      .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    output.stdout
+  };
+  assert!(!gen_output(&[]).is_empty());
+  assert_eq!(String::from_utf8(gen_output(&["--quiet"])).unwrap(), "");
+  assert!(root.join("out/codex/a.codex.patch").is_file());
+  fs::remove_dir_all(&root).unwrap();
+}